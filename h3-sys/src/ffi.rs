@@ -1,5 +1,8 @@
 /* automatically generated by rust-bindgen */
 
+pub const H3_VERSION_MAJOR: u32 = 3;
+pub const H3_VERSION_MINOR: u32 = 7;
+pub const H3_VERSION_PATCH: u32 = 2;
 pub const MAX_CELL_BNDRY_VERTS: u32 = 10;
 pub type H3Index = u64;
 #[repr(C)]
@@ -167,12 +170,39 @@ extern "C" {
 extern "C" {
     pub fn radsToDegs(radians: f64) -> f64;
 }
+extern "C" {
+    pub fn pointDistRads(a: *const GeoCoord, b: *const GeoCoord) -> f64;
+}
+extern "C" {
+    pub fn pointDistKm(a: *const GeoCoord, b: *const GeoCoord) -> f64;
+}
+extern "C" {
+    pub fn pointDistM(a: *const GeoCoord, b: *const GeoCoord) -> f64;
+}
 extern "C" {
     pub fn hexAreaKm2(res: ::std::os::raw::c_int) -> f64;
 }
 extern "C" {
     pub fn hexAreaM2(res: ::std::os::raw::c_int) -> f64;
 }
+extern "C" {
+    pub fn cellAreaRads2(h: H3Index) -> f64;
+}
+extern "C" {
+    pub fn cellAreaKm2(h: H3Index) -> f64;
+}
+extern "C" {
+    pub fn cellAreaM2(h: H3Index) -> f64;
+}
+extern "C" {
+    pub fn exactEdgeLengthRads(edge: H3Index) -> f64;
+}
+extern "C" {
+    pub fn exactEdgeLengthKm(edge: H3Index) -> f64;
+}
+extern "C" {
+    pub fn exactEdgeLengthM(edge: H3Index) -> f64;
+}
 extern "C" {
     pub fn edgeLengthKm(res: ::std::os::raw::c_int) -> f64;
 }
@@ -182,6 +212,9 @@ extern "C" {
 extern "C" {
     pub fn numHexagons(res: ::std::os::raw::c_int) -> i64;
 }
+extern "C" {
+    pub fn res0IndexCount() -> ::std::os::raw::c_int;
+}
 extern "C" {
     pub fn getRes0Indexes(out: *mut H3Index);
 }
@@ -212,6 +245,9 @@ extern "C" {
 extern "C" {
     pub fn h3ToChildren(h: H3Index, childRes: ::std::os::raw::c_int, children: *mut H3Index);
 }
+extern "C" {
+    pub fn h3ToCenterChild(h: H3Index, childRes: ::std::os::raw::c_int) -> H3Index;
+}
 extern "C" {
     pub fn compact(
         h3Set: *const H3Index,
@@ -1,4 +1,7 @@
 pub use crate::ffi::{
+    cellAreaKm2,
+    cellAreaM2,
+    cellAreaRads2,
     compact,
     //    h3SetToMultiPolygon,
     degsToRads,
@@ -6,6 +9,9 @@ pub use crate::ffi::{
     //    edgeLength,
     edgeLengthKm,
     edgeLengthM,
+    exactEdgeLengthKm,
+    exactEdgeLengthM,
+    exactEdgeLengthRads,
     experimentalH3ToLocalIj,
     experimentalLocalIjToH3,
     geoToH3,
@@ -27,6 +33,7 @@ pub use crate::ffi::{
     h3Line,
     h3LineSize,
     h3SetToLinkedGeo,
+    h3ToCenterChild,
     h3ToChildren,
     h3ToGeo,
     h3ToGeoBoundary,
@@ -48,8 +55,12 @@ pub use crate::ffi::{
     maxPolyfillSize,
     maxUncompactSize,
     numHexagons,
+    pointDistKm,
+    pointDistM,
+    pointDistRads,
     polyfill,
     radsToDegs,
+    res0IndexCount,
     stringToH3,
     uncompact,
     CoordIJ,
@@ -62,6 +73,9 @@ pub use crate::ffi::{
     LinkedGeoCoord,
     LinkedGeoLoop,
     LinkedGeoPolygon,
+    H3_VERSION_MAJOR,
+    H3_VERSION_MINOR,
+    H3_VERSION_PATCH,
     MAX_CELL_BNDRY_VERTS,
 };
 
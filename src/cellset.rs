@@ -0,0 +1,308 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sets of H3 indexes
+//!
+//! A set of H3 indexes (e.g., a region covering), along with a compact binary
+//! encoding for storage and a plain-text encoding of one index per line.
+
+use crate::errors::*;
+use crate::hierarchy::*;
+use crate::index::*;
+use crate::resolution::*;
+use crate::types::*;
+
+use geo_types::Point;
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+
+/// A sorted, de-duplicated set of H3 indexes
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CellSet(Vec<H3Index>);
+
+impl CellSet {
+    /// Construct a CellSet, sorting and de-duplicating the indexes.
+    pub fn new(mut cells: Vec<H3Index>) -> Self {
+        cells.retain(|i| *i != H3Index(0));
+        cells.sort();
+        cells.dedup();
+        CellSet(cells)
+    }
+
+    /// Returns the indexes in the set, in sorted order.
+    pub fn cells(&self) -> &[H3Index] {
+        &self.0
+    }
+
+    /// Encodes the set by merging complete groups of siblings into their
+    /// parents (see `merge_complete_parents`), then writing the deltas between
+    /// consecutive (sorted) indexes as zigzag varints. Nearby cells have
+    /// nearby indexes, so contiguous regions encode much smaller than raw
+    /// 64-bit values. Decoding yields the merged set, which may be of mixed
+    /// resolution.
+    pub fn to_rle_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut prev = 0u64;
+        for cell in merge_complete_parents(&self.0) {
+            let delta = cell.0.wrapping_sub(prev) as i64;
+            write_varint(&mut bytes, ((delta << 1) ^ (delta >> 63)) as u64);
+            prev = cell.0;
+        }
+        bytes
+    }
+
+    /// Decodes a set written by `to_rle_bytes`, validating each index.
+    pub fn from_rle_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut cells = Vec::new();
+        let mut prev = 0u64;
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let zigzag = read_varint(bytes, &mut pos)?;
+            let delta = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+            prev = prev.wrapping_add(delta as u64);
+            cells.push(H3Index::new(prev)?);
+        }
+        Ok(CellSet::new(cells))
+    }
+}
+
+/// A sorted set of H3 indexes supporting hierarchical range queries
+///
+/// Indexes are ordered by their bits with the resolution cleared, i.e., by
+/// base cell and then digits (with unused digits being 7). In this order a
+/// cell's descendants at every resolution form a contiguous range ending at
+/// the cell itself, unlike in plain integer order, which groups indexes by
+/// resolution first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CellIndex(Vec<H3Index>);
+
+/// Bit offset of the resolution in an index.
+const RES_OFFSET: u64 = 52;
+
+/// Bits of the digits below the given resolution.
+fn digits_below(res: u64) -> u64 {
+    (1 << ((15 - res) * 3)) - 1
+}
+
+/// Sort key placing descendants contiguously before their ancestor.
+fn hierarchical_key(index: &H3Index) -> u64 {
+    index.0 & !(0xf << RES_OFFSET)
+}
+
+impl CellIndex {
+    /// Construct a CellIndex, sorting and de-duplicating the indexes.
+    pub fn new(mut cells: Vec<H3Index>) -> Self {
+        cells.retain(|i| *i != H3Index(0));
+        cells.sort_by_key(hierarchical_key);
+        cells.dedup();
+        CellIndex(cells)
+    }
+
+    /// Returns the indexes in hierarchical order.
+    pub fn cells(&self) -> &[H3Index] {
+        &self.0
+    }
+
+    /// Is the index stored?
+    pub fn contains(&self, index: &H3Index) -> bool {
+        self.0
+            .binary_search_by_key(&hierarchical_key(index), hierarchical_key)
+            .is_ok()
+    }
+
+    /// Returns the stored indexes from lo (inclusive) to hi (exclusive) in
+    /// hierarchical order.
+    pub fn range(&self, range: std::ops::Range<H3Index>) -> &[H3Index] {
+        let (lo, hi) = (hierarchical_key(&range.start), hierarchical_key(&range.end));
+        let start = self.0.partition_point(|i| hierarchical_key(i) < lo);
+        let end = self.0.partition_point(|i| hierarchical_key(i) < hi);
+        &self.0[start..end.max(start)]
+    }
+
+    /// Returns the stored descendants of the parent (including the parent
+    /// itself, if stored), found by binary search.
+    pub fn covering_query(&self, parent: &H3Index) -> &[H3Index] {
+        let res = (parent.0 >> RES_OFFSET) & 0xf;
+        let hi = hierarchical_key(parent);
+        let lo = hi & !digits_below(res);
+        let start = self.0.partition_point(|i| hierarchical_key(i) < lo);
+        let end = self.0.partition_point(|i| hierarchical_key(i) <= hi);
+        &self.0[start..end]
+    }
+}
+
+/// Appends the LEB128 varint encoding of the value.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads a LEB128 varint starting at pos, advancing pos past it.
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos).ok_or(Error::UnableToDecode)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::UnableToDecode)
+}
+
+/// Reads indexes written one per line in hex (e.g., "8928308280fffff"),
+/// skipping blank lines and comments starting with '#'. Returns
+/// `InvalidLine` with the line number for a line that isn't a valid index.
+pub fn read_cells<R: BufRead>(r: R) -> Result<Vec<H3Index>> {
+    let mut cells = Vec::new();
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let text = line.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let cell = u64::from_str_radix(text, 16)
+            .ok()
+            .and_then(|value| H3Index::new(value).ok())
+            .ok_or_else(|| Error::InvalidLine(i + 1, text.to_string()))?;
+        cells.push(cell);
+    }
+    Ok(cells)
+}
+
+/// Writes the indexes one per line in hex, as read by `read_cells`.
+pub fn write_cells<W: Write>(cells: &[H3Index], w: &mut W) -> Result<()> {
+    for cell in cells {
+        writeln!(w, "{:x}", cell)?;
+    }
+    Ok(())
+}
+
+/// Does the set (e.g., a service area covering) contain the point? Indexes the
+/// point at the resolution and checks membership, so the resolution must
+/// match that of the set. Returns false if the point can't be indexed.
+pub fn set_contains_point(cells: &HashSet<H3Index>, p: Point<f64>, res: GridResolution) -> bool {
+    p.to_h3_index(res)
+        .map(|i| cells.contains(&i))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rle_round_trip() {
+        let cells = H3Index(0x8928308280fffff).k_ring_indices(10);
+        let set = CellSet::new(cells.clone());
+        let bytes = set.to_rle_bytes();
+        let decoded = CellSet::from_rle_bytes(&bytes).unwrap();
+        assert_eq!(decoded, CellSet::new(cells.compact().unwrap()));
+        // The decoded set is of mixed resolution, and re-encodes as is.
+        assert_eq!(decoded.to_rle_bytes(), bytes);
+        // Contiguous blocks encode much smaller than raw 64-bit values.
+        assert!(bytes.len() * 2 < decoded.cells().len() * 8);
+        assert_eq!(
+            CellSet::from_rle_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::UnableToDecode)
+        );
+    }
+
+    #[test]
+    fn test_rle_round_trip_mixed_resolution() {
+        let parent = H3Index(0x87283472bffffff);
+        let mut cells = vec![parent.clone()];
+        cells
+            .extend(parent.k_ring_indices(1)[1].children_ordered(GridResolution::Z9)[1..].to_vec());
+        let set = CellSet::new(cells);
+        let bytes = set.to_rle_bytes();
+        let decoded = CellSet::from_rle_bytes(&bytes).unwrap();
+        // The six complete groups of Z9 siblings come back as their Z8
+        // parents; the incomplete group stays at Z9.
+        assert_eq!(decoded, CellSet::new(merge_complete_parents(set.cells())));
+        assert_eq!(decoded.cells().len(), 1 + 6 + 6);
+        assert_eq!(decoded.to_rle_bytes(), bytes);
+        assert_eq!(CellSet::new(vec![]).to_rle_bytes(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_covering_query() {
+        let parent = H3Index(0x8828308281fffff);
+        let children = parent.children_ordered(GridResolution::Z9);
+        let grandchild = children[2].children_ordered(GridResolution::Z10)[3].clone();
+        let mut cells: Vec<H3Index> = parent.k_ring_indices(2);
+        cells.extend(children.clone());
+        cells.push(grandchild.clone());
+        cells.push(parent.parent(GridResolution::Z7));
+        for neighbor in parent.ring_at(1) {
+            cells.extend(neighbor.children(GridResolution::Z9));
+        }
+        let index = CellIndex::new(cells.clone());
+        assert!(cells.iter().all(|c| index.contains(c)));
+        assert!(!index.contains(&parent.ring_at(3)[0]));
+        let mut expected: Vec<H3Index> = cells
+            .iter()
+            .filter(|c| parent.contains_cell(c))
+            .cloned()
+            .collect();
+        expected.sort();
+        expected.dedup();
+        let mut actual = index.covering_query(&parent).to_vec();
+        actual.sort();
+        assert_eq!(actual, expected);
+        assert_eq!(expected.len(), 1 + 7 + 1);
+        let children_range = index.range(children[0].clone()..parent.clone());
+        assert!(children_range.contains(&grandchild));
+        assert!(!children_range.contains(&parent));
+    }
+
+    #[test]
+    fn test_set_contains_point() {
+        let res = GridResolution::Z9;
+        let cells: HashSet<H3Index> = H3Index(0x8928308280fffff)
+            .k_ring_indices(1)
+            .into_iter()
+            .collect();
+        let inside = Point::new(-122.418307270836, 37.7752702151959);
+        assert!(set_contains_point(&cells, inside, res));
+        let outside = Point::new(-122.40, 37.79);
+        assert!(!set_contains_point(&cells, outside, res));
+        assert!(!set_contains_point(&cells, inside, GridResolution::Z8));
+        assert!(!set_contains_point(&cells, Point::new(f64::NAN, 0.), res));
+    }
+
+    #[test]
+    fn test_read_write_cells() {
+        let cells = H3Index(0x8928308280fffff).k_ring_indices(1);
+        let mut buf = Vec::new();
+        write_cells(&cells, &mut buf).unwrap();
+        assert_eq!(read_cells(&buf[..]).unwrap(), cells);
+
+        let text = "# covering\n8928308280fffff\n\n  821c07fffffffff  \n";
+        assert_eq!(
+            read_cells(text.as_bytes()).unwrap(),
+            vec![H3Index(0x8928308280fffff), H3Index(0x821c07fffffffff)]
+        );
+        let text = "8928308280fffff\n# comment\n5004295803a88\n";
+        assert_eq!(
+            read_cells(text.as_bytes()),
+            Err(Error::InvalidLine(3, "5004295803a88".to_string()))
+        );
+    }
+}
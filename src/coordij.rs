@@ -0,0 +1,175 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local IJ coordinates
+//!
+//! Two-dimensional coordinates of cells relative to an origin cell. These are
+//! only defined near the origin (e.g., not across pentagon distortion), and
+//! aren't comparable between different origins. Based on h3's experimental
+//! local IJ API.
+
+use crate::errors::*;
+use crate::resolution::*;
+use crate::types::*;
+
+/// IJ coordinates of a cell relative to an origin cell
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CoordIJ {
+    pub i: i32,
+    pub j: i32,
+}
+
+impl From<h3_sys::CoordIJ> for CoordIJ {
+    fn from(c: h3_sys::CoordIJ) -> CoordIJ {
+        CoordIJ { i: c.i, j: c.j }
+    }
+}
+
+impl From<CoordIJ> for h3_sys::CoordIJ {
+    fn from(c: CoordIJ) -> h3_sys::CoordIJ {
+        h3_sys::CoordIJ { i: c.i, j: c.j }
+    }
+}
+
+impl H3Index {
+    /// Returns the IJ coordinates of this index relative to the origin.
+    /// Returns error if they can't be computed, e.g., the indexes are too far
+    /// apart or separated by a pentagon.
+    pub fn to_local_ij(&self, origin: &H3Index) -> Result<CoordIJ> {
+        let mut ij = h3_sys::CoordIJ::default();
+        let err = unsafe { h3_sys::experimentalH3ToLocalIj(origin.0, self.0, &mut ij) };
+        if err == 0 {
+            Ok(ij.into())
+        } else {
+            Err(Error::IncompatibleIndices(origin.clone(), self.clone()))
+        }
+    }
+
+    /// Returns the index at the IJ coordinates relative to the origin.
+    /// Returns error if there's no such index, in which case the second index
+    /// of the error is unset.
+    pub fn from_local_ij(origin: &H3Index, ij: CoordIJ) -> Result<H3Index> {
+        let ij: h3_sys::CoordIJ = ij.into();
+        let mut out: h3_sys::H3Index = 0;
+        let err = unsafe { h3_sys::experimentalLocalIjToH3(origin.0, &ij, &mut out) };
+        if err == 0 {
+            Ok(H3Index(out))
+        } else {
+            Err(Error::IncompatibleIndices(origin.clone(), H3Index(0)))
+        }
+    }
+
+    /// Returns an iterator over every index at the resolution within this
+    /// index's base cell, each paired with its IJ coordinates relative to the
+    /// base cell's center index at that resolution, starting from the center.
+    /// A pentagon base cell has no descendants in its deleted K axis
+    /// subsequence, so those coordinates are simply absent. Indexes that h3
+    /// can't give coordinates are omitted, though every descendant of a base
+    /// cell has them in practice.
+    ///
+    /// A base cell has about 7^res descendants, i.e., billions from Z12, so
+    /// this returns an iterator producing the indexes lazily (see
+    /// `children_iter`) rather than a `Vec`; collect it for coarse
+    /// resolutions. Returns error if this index is invalid.
+    pub fn base_cell_local_grid(
+        &self,
+        res: GridResolution,
+    ) -> Result<impl Iterator<Item = (CoordIJ, H3Index)>> {
+        if !self.is_valid() {
+            return Err(Error::InvalidIndexArgument(self.0));
+        }
+        let base = self.parent(GridResolution::Z0);
+        let origin = base.refine_center(res as u8)?;
+        Ok(base
+            .children_iter(res)
+            .filter_map(move |cell| cell.to_local_ij(&origin).ok().map(|ij| (ij, cell))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_ij_round_trip() {
+        let origin = H3Index(0x8928308280fffff);
+        assert_eq!(
+            H3Index::from_local_ij(&origin, origin.to_local_ij(&origin).unwrap()),
+            Ok(origin.clone())
+        );
+        for neighbor in origin.k_ring_indices(2) {
+            let ij = neighbor.to_local_ij(&origin).unwrap();
+            assert_eq!(H3Index::from_local_ij(&origin, ij), Ok(neighbor));
+        }
+        // Indexes on opposite sides of the globe have no local coordinates.
+        let far = H3Index(0x8003fffffffffff);
+        assert_eq!(
+            far.to_local_ij(&origin),
+            Err(Error::IncompatibleIndices(origin.clone(), far.clone()))
+        );
+        let unreachable = CoordIJ { i: 1 << 20, j: 0 };
+        assert!(H3Index::from_local_ij(&origin, unreachable).is_err());
+    }
+
+    #[test]
+    fn test_base_cell_local_grid() {
+        let index = H3Index(0x8928308280fffff);
+        let grid: Vec<_> = index
+            .base_cell_local_grid(GridResolution::Z2)
+            .unwrap()
+            .collect();
+        assert_eq!(grid.len(), 49);
+        let center = index.parent(GridResolution::Z0).refine_center(2).unwrap();
+        assert!(grid.contains(&(CoordIJ { i: 0, j: 0 }, center)));
+        for (ij, cell) in &grid {
+            assert_eq!(cell.base_cell(), index.base_cell());
+            assert_eq!(cell.to_local_ij(&grid[0].1), Ok(*ij));
+        }
+
+        let pentagon = H3Index(0x821c07fffffffff);
+        let grid: Vec<_> = pentagon
+            .base_cell_local_grid(GridResolution::Z2)
+            .unwrap()
+            .collect();
+        // 1 + 5 + 5 * 7 cells, skipping the deleted subsequence.
+        assert_eq!(grid.len(), 41);
+        let children = pentagon
+            .parent(GridResolution::Z0)
+            .children_ordered(GridResolution::Z2);
+        assert_eq!(children.len(), grid.len());
+        for child in &children {
+            assert!(grid.iter().any(|(_, cell)| cell == child));
+        }
+        assert_eq!(
+            grid[0],
+            (
+                CoordIJ { i: 0, j: 0 },
+                pentagon
+                    .parent(GridResolution::Z0)
+                    .refine_center(2)
+                    .unwrap()
+            )
+        );
+
+        // Deep grids are only enumerated as far as they're consumed.
+        let mut deep = index.base_cell_local_grid(GridResolution::Z15).unwrap();
+        assert_eq!(deep.next().map(|(ij, _)| ij), Some(CoordIJ { i: 0, j: 0 }));
+        assert_eq!(deep.take(10).count(), 10);
+        assert!(matches!(
+            H3Index(0x5004295803a88).base_cell_local_grid(GridResolution::Z2),
+            Err(Error::InvalidIndexArgument(0x5004295803a88))
+        ));
+    }
+}
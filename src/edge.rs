@@ -0,0 +1,285 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Unidirectional edge functions
+//!
+//! Unidirectional edges allow encoding the directed edge from one cell to a
+//! neighboring cell.
+
+use crate::errors::*;
+use crate::raw::*;
+use crate::types::*;
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// A unique index for a directed edge between two neighboring H3 cells
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct EdgeIndex(pub h3_sys::H3Index);
+
+impl EdgeIndex {
+    /// Is the given EdgeIndex a valid unidirectional edge?
+    pub fn is_valid(&self) -> bool {
+        unsafe { h3_sys::h3UnidirectionalEdgeIsValid(self.0) != 0 }
+    }
+
+    /// Returns the origin cell of the edge.
+    pub fn origin(&self) -> H3Index {
+        unsafe { H3Index(h3_sys::getOriginH3IndexFromUnidirectionalEdge(self.0)) }
+    }
+
+    /// Returns the destination cell of the edge.
+    pub fn destination(&self) -> H3Index {
+        unsafe { H3Index(h3_sys::getDestinationH3IndexFromUnidirectionalEdge(self.0)) }
+    }
+
+    /// Returns the boundary of the edge in lat/lon coordinates: the two
+    /// vertices shared by its cells, plus any distortion vertices in between.
+    pub fn boundary(&self) -> LineString<f64> {
+        let mut gb = h3_sys::GeoBoundary::default();
+        unsafe {
+            h3_sys::getH3UnidirectionalEdgeBoundary(self.0, &mut gb);
+        }
+        GeoBoundary(gb).into()
+    }
+
+    /// Exact length of the edge in kilometers.
+    pub fn length_km(&self) -> f64 {
+        unsafe { h3_sys::exactEdgeLengthKm(self.0) }
+    }
+
+    /// Exact length of the edge in meters.
+    pub fn length_m(&self) -> f64 {
+        unsafe { h3_sys::exactEdgeLengthM(self.0) }
+    }
+
+    /// Returns the (origin, destination) cells of the edge.
+    pub fn endpoints(&self) -> (H3Index, H3Index) {
+        let mut buf: [h3_sys::H3Index; 2] = [0; 2];
+        unsafe {
+            h3_sys::getH3IndexesFromUnidirectionalEdge(self.0, buf.as_mut_ptr());
+        }
+        (H3Index(buf[0]), H3Index(buf[1]))
+    }
+}
+
+// NB: Edge and cell indexes share a representation but differ in the meaning
+// of their bits, so conversions between them check validity.
+
+impl TryFrom<H3Index> for EdgeIndex {
+    type Error = Error;
+
+    fn try_from(index: H3Index) -> Result<Self> {
+        let edge = EdgeIndex(index.0);
+        if edge.is_valid() {
+            Ok(edge)
+        } else {
+            Err(Error::InvalidIndexArgument(index.0))
+        }
+    }
+}
+
+impl TryFrom<EdgeIndex> for H3Index {
+    type Error = Error;
+
+    fn try_from(edge: EdgeIndex) -> Result<Self> {
+        H3Index::new(edge.0)
+    }
+}
+
+impl H3Index {
+    /// Are the indexes neighbors, i.e., do they share an edge? An index isn't
+    /// its own neighbor, and indexes at different resolutions aren't
+    /// neighbors.
+    pub fn is_neighbor(&self, other: &H3Index) -> bool {
+        unsafe { h3_sys::h3IndexesAreNeighbors(self.0, other.0) != 0 }
+    }
+
+    /// Returns the unidirectional edge from this index to the given
+    /// destination. Returns error if the indices aren't neighbors.
+    pub fn unidirectional_edge_to(&self, dest: &H3Index) -> Result<EdgeIndex> {
+        let edge = unsafe { h3_sys::getH3UnidirectionalEdge(self.0, dest.0) };
+        if edge == 0 {
+            Err(Error::IncompatibleIndices(self.clone(), dest.clone()))
+        } else {
+            Ok(EdgeIndex(edge))
+        }
+    }
+
+    /// Returns the unidirectional edges originating from this index: six for
+    /// hexagons and five for pentagons.
+    pub fn unidirectional_edges(&self) -> Vec<EdgeIndex> {
+        // h3 leaves a zero entry in place of the missing pentagon edge.
+        let mut buf: [h3_sys::H3Index; 6] = [0; 6];
+        unsafe {
+            h3_sys::getH3UnidirectionalEdgesFromHexagon(self.0, buf.as_mut_ptr());
+        }
+        buf.iter()
+            .filter(|e| **e != 0)
+            .map(|e| EdgeIndex(*e))
+            .collect()
+    }
+
+    /// Returns, for each ring distance d < k, the edges leading from cells in
+    /// k-ring d to their neighbors in k-ring d + 1.
+    pub fn edge_rings(&self, k: i32) -> Vec<Vec<EdgeIndex>> {
+        let rings = self.k_ring_distances(k);
+        let mut result = Vec::new();
+        for d in 0..rings.len().saturating_sub(1) {
+            let outer: HashSet<&H3Index> = rings[d + 1].iter().collect();
+            let mut edges = Vec::new();
+            for cell in &rings[d] {
+                for neighbor in cell.k_ring_indices(1) {
+                    if outer.contains(&neighbor) {
+                        if let Ok(edge) = cell.unidirectional_edge_to(&neighbor) {
+                            edges.push(edge);
+                        }
+                    }
+                }
+            }
+            result.push(edges);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::GridResolution;
+    use geo_types::Point;
+
+    #[test]
+    fn test_unidirectional_edge_to() {
+        // San Francisco pair from the h3-js test suite.
+        let origin = H3Index(0x891ea6d6533ffff);
+        let dest = H3Index(0x891ea6d65afffff);
+        assert_eq!(
+            origin.unidirectional_edge_to(&dest),
+            Ok(EdgeIndex(0x1591ea6d6533ffff))
+        );
+        assert_eq!(
+            origin.unidirectional_edge_to(&origin),
+            Err(Error::IncompatibleIndices(origin.clone(), origin.clone()))
+        );
+        let far = H3Index(0x8928308280fffff);
+        assert!(origin.unidirectional_edge_to(&far).is_err());
+    }
+
+    #[test]
+    fn test_is_neighbor() {
+        let origin = H3Index(0x8928308280fffff);
+        for cell in origin.k_ring_indices(1) {
+            assert_eq!(origin.is_neighbor(&cell), cell != origin);
+        }
+        assert!(!origin.is_neighbor(&origin));
+        let ring2 = origin.hex_ring(2).unwrap();
+        assert!(ring2.iter().all(|cell| !origin.is_neighbor(cell)));
+        let child = origin.k_ring_indices(1)[1].children_ordered(GridResolution::Z10)[0].clone();
+        assert!(!origin.is_neighbor(&child));
+    }
+
+    #[test]
+    fn test_edge_endpoints() {
+        let origin = H3Index(0x891ea6d6533ffff);
+        let dest = H3Index(0x891ea6d65afffff);
+        let edge = origin.unidirectional_edge_to(&dest).unwrap();
+        assert_eq!(edge.origin(), origin);
+        assert_eq!(edge.destination(), dest);
+        assert_eq!(edge.endpoints(), (origin, dest));
+    }
+
+    #[test]
+    fn test_edge_boundary() {
+        let origin = H3Index(0x891ea6d6533ffff);
+        let dest = H3Index(0x891ea6d65afffff);
+        let boundary = origin.unidirectional_edge_to(&dest).unwrap().boundary();
+        assert_eq!(boundary.num_coords(), 2);
+        let eps = GridResolution::Z9.coordinate_tolerance();
+        let dest_vertices = LineString::from(dest).into_points();
+        let shared: Vec<Point<f64>> = LineString::from(origin)
+            .into_points()
+            .into_iter()
+            .filter(|p| {
+                dest_vertices
+                    .iter()
+                    .any(|q| (p.lat() - q.lat()).abs() < eps && (p.lng() - q.lng()).abs() < eps)
+            })
+            .collect();
+        assert_eq!(shared.len(), 2);
+        for p in boundary.points_iter() {
+            assert!(shared
+                .iter()
+                .any(|q| { (p.lat() - q.lat()).abs() < eps && (p.lng() - q.lng()).abs() < eps }));
+        }
+    }
+
+    #[test]
+    fn test_edge_length() {
+        let origin = H3Index(0x891ea6d6533ffff);
+        let dest = H3Index(0x891ea6d65afffff);
+        let edge = origin.unidirectional_edge_to(&dest).unwrap();
+        assert_relative_eq!(
+            edge.length_m(),
+            edge.length_km() * 1.0e3,
+            max_relative = 1e-9
+        );
+        let avg = GridResolution::Z9.edge_length();
+        assert_ne!(edge.length_m(), avg);
+        assert_relative_eq!(edge.length_m(), avg, max_relative = 0.5);
+    }
+
+    #[test]
+    fn test_edge_is_valid() {
+        let origin = H3Index(0x891ea6d6533ffff);
+        let edge = origin
+            .unidirectional_edge_to(&H3Index(0x891ea6d65afffff))
+            .unwrap();
+        assert!(edge.is_valid());
+        assert!(!EdgeIndex(origin.0).is_valid());
+        assert_eq!(EdgeIndex::try_from(H3Index(edge.0)), Ok(edge.clone()));
+        assert_eq!(
+            EdgeIndex::try_from(origin.clone()),
+            Err(Error::InvalidIndexArgument(origin.0))
+        );
+        assert_eq!(H3Index::try_from(EdgeIndex(origin.0)), Ok(origin));
+        assert!(H3Index::try_from(edge).is_err());
+    }
+
+    #[test]
+    fn test_unidirectional_edges() {
+        let origin = H3Index(0x891ea6d6533ffff);
+        let edges = origin.unidirectional_edges();
+        assert_eq!(edges.len(), 6);
+        assert!(edges.iter().all(|e| e.is_valid() && e.origin() == origin));
+        let pentagon = H3Index(0x821c07fffffffff);
+        let edges = pentagon.unidirectional_edges();
+        assert_eq!(edges.len(), 5);
+        assert!(edges.iter().all(|e| e.is_valid() && e.origin() == pentagon));
+    }
+
+    #[test]
+    fn test_edge_rings() {
+        let rings = H3Index(0x8928308280fffff).edge_rings(1);
+        assert_eq!(rings.len(), 1);
+        assert_eq!(rings[0].len(), 6);
+        let unique: HashSet<&EdgeIndex> = rings[0].iter().collect();
+        assert_eq!(unique.len(), 6);
+        let rings = H3Index(0x8928308280fffff).edge_rings(2);
+        assert_eq!(rings.len(), 2);
+        // Each cell in ring 1 has three neighbors in ring 2.
+        assert_eq!(rings[1].len(), 18);
+    }
+}
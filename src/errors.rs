@@ -16,26 +16,44 @@
 //! Shared error types
 
 pub use crate::index::*;
+use crate::resolution::GridResolution;
 
-pub use geo_types::Point;
+pub use geo_types::{LineString, Point, Polygon};
 
 /// `h3-rs`-specific errors
 #[derive(Debug, PartialEq)]
 pub enum Error {
     /// The integer value is not valid as an H3Index.
     InvalidIndexArgument(u64),
+    /// The string is neither a hex nor a decimal H3Index.
+    InvalidIndexString(String),
     /// The binary operation involving the two indices failed. this can happen
     /// because the two indexes are not comparable (different resolutions), too
     /// far apart, or are separated by pentagonal distortion.
     IncompatibleIndices(H3Index, H3Index),
-    /// The point could not be indexed.
-    UnableToIndex(Point<f64>),
+    /// The point could not be indexed at the given resolution. Since
+    /// resolutions are always valid by construction, this generally indicates
+    /// a coordinate that h3 can't handle (e.g., non-finite lat/lon).
+    UnableToIndex(Point<f64>, GridResolution),
     /// Unable to serialize
     UnableToSerialize(H3Index),
+    /// Unable to decode binary-encoded indexes
+    UnableToDecode,
+    /// The string isn't a valid hierarchical index path
+    InvalidPathString(String),
+    /// The (1-based) numbered line of input isn't a valid hex index
+    InvalidLine(usize, String),
     /// Unable to parse string value to integer
     ParseIntError(std::num::ParseIntError),
     /// Invalid resolution argument
     InvalidResolutionArgument(i32),
+    /// The resolution range is empty: its start is finer than its end
+    InvalidResolutionRange(GridResolution, GridResolution),
+    /// The zoom level is beyond `MAX_ZOOM`
+    InvalidZoom(u8),
+    /// The (z, x, y) tile doesn't exist: the zoom is beyond `MAX_ZOOM`, or x
+    /// or y is outside the zoom's grid
+    InvalidTile(u8, u32, u32),
     /// Unable to compute line between two H3 indices
     UnableToComputeH3Line(H3Index, H3Index),
     /// Unable to compute a traversal (hex range or hex ring) centered at H3
@@ -43,35 +61,144 @@ pub enum Error {
     UnableToComputeTraversal(H3Index, i32),
     /// Unable to compact (or uncompact) the given set of H3 indices
     UnableToCompact(Vec<H3Index>),
+    /// Unable to dissolve the given set of H3 indices into polygons
+    UnableToDissolve(Vec<H3Index>),
+    /// The polygon can't be used as a region, e.g., because its exterior ring
+    /// intersects itself.
+    InvalidPolygon(Polygon<f64>),
+    /// The ring is degenerate: it has fewer than three distinct vertices or
+    /// contains non-finite coordinates.
+    InvalidRing(LineString<f64>),
+    /// Unable to read input
+    IoError(std::io::ErrorKind),
+    /// Unable to parse or convert GeoJSON
+    GeoJsonError(geojson::Error),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let expression = match self {
-            Error::InvalidIndexArgument(arg) => format!("Invalid index arg={}", arg),
+        match self {
+            Error::InvalidIndexArgument(arg) => write!(f, "Invalid index arg={}", arg),
+            Error::InvalidIndexString(s) => write!(f, "Invalid index string={:?}", s),
             Error::IncompatibleIndices(left, right) => {
-                format!("Incompatible H3 indices: {} and {}", left, right)
+                write!(f, "Incompatible H3 indices: {} and {}", left, right)
             }
-            Error::UnableToIndex(point) => format!(
-                "Unable to index point (lat={}, lon={})",
+            Error::UnableToIndex(point, res) => write!(
+                f,
+                "Unable to index point (lat={}, lon={}) res={:?}",
                 point.lat(),
-                point.lng()
+                point.lng(),
+                res
             ),
-            Error::UnableToSerialize(index) => format!("Unable to serialize h3index={}", index),
-            Error::ParseIntError(error) => format!("Unable to parse integer. error={}", error),
+            Error::UnableToSerialize(index) => write!(f, "Unable to serialize h3index={}", index),
+            Error::UnableToDecode => f.write_str("Unable to decode indexes"),
+            Error::InvalidPathString(path) => write!(f, "Invalid index path={}", path),
+            Error::InvalidLine(number, line) => {
+                write!(f, "Invalid index on line {}: {}", number, line)
+            }
+            Error::ParseIntError(error) => write!(f, "Unable to parse integer. error={}", error),
             Error::InvalidResolutionArgument(arg) => {
-                format!("Unable to parse integer to create resolution. arg={}", arg)
+                write!(
+                    f,
+                    "Unable to parse integer to create resolution. arg={}",
+                    arg
+                )
+            }
+            Error::InvalidResolutionRange(start, end) => {
+                write!(
+                    f,
+                    "Invalid resolution range start={:?} end={:?}",
+                    start, end
+                )
             }
-            Error::UnableToComputeH3Line(left, right) => format!(
+            Error::InvalidZoom(zoom) => write!(f, "Invalid zoom={}", zoom),
+            Error::InvalidTile(z, x, y) => write!(f, "Invalid tile z={} x={} y={}", z, x, y),
+            Error::UnableToComputeH3Line(left, right) => write!(
+                f,
                 "Unable to compute line between indices: left={} right={}",
                 left, right
             ),
             Error::UnableToComputeTraversal(index, k) => {
-                format!("Unable to compute traversal index={} k={}", index, k)
+                write!(f, "Unable to compute traversal index={} k={}", index, k)
             }
-            Error::UnableToCompact(_) => format!("Unable to compact/uncompact set"),
-        };
-        write!(f, "{ }", expression)
+            Error::UnableToCompact(_) => f.write_str("Unable to compact/uncompact set"),
+            Error::UnableToDissolve(_) => f.write_str("Unable to dissolve set"),
+            Error::InvalidPolygon(_) => f.write_str("Invalid polygon"),
+            Error::InvalidRing(ring) => write!(f, "Invalid ring with {} coords", ring.num_coords()),
+            Error::IoError(kind) => write!(f, "Unable to read input. error={:?}", kind),
+            Error::GeoJsonError(error) => write!(f, "Unable to parse GeoJSON. error={}", error),
+        }
+    }
+}
+
+/// Serializes as a tagged object, e.g. `{"type":"InvalidIndexArgument","value":123}`.
+/// Variants with several fields serialize their value as a tuple; foreign
+/// error payloads serialize as strings; `UnableToDecode` has no value.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        fn tagged<S: serde::Serializer, T: serde::Serialize>(
+            serializer: S,
+            tag: &'static str,
+            value: Option<T>,
+        ) -> std::result::Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Error", 2)?;
+            state.serialize_field("type", tag)?;
+            match value {
+                Some(value) => state.serialize_field("value", &value)?,
+                None => state.skip_field("value")?,
+            }
+            state.end()
+        }
+        match self {
+            Error::InvalidIndexArgument(arg) => {
+                tagged(serializer, "InvalidIndexArgument", Some(arg))
+            }
+            Error::InvalidIndexString(s) => tagged(serializer, "InvalidIndexString", Some(s)),
+            Error::IncompatibleIndices(left, right) => {
+                tagged(serializer, "IncompatibleIndices", Some((left, right)))
+            }
+            Error::UnableToIndex(point, res) => {
+                tagged(serializer, "UnableToIndex", Some((point, res)))
+            }
+            Error::UnableToSerialize(index) => tagged(serializer, "UnableToSerialize", Some(index)),
+            Error::UnableToDecode => tagged::<S, ()>(serializer, "UnableToDecode", None),
+            Error::InvalidPathString(path) => tagged(serializer, "InvalidPathString", Some(path)),
+            Error::InvalidLine(number, line) => {
+                tagged(serializer, "InvalidLine", Some((number, line)))
+            }
+            Error::ParseIntError(error) => {
+                tagged(serializer, "ParseIntError", Some(error.to_string()))
+            }
+            Error::InvalidResolutionArgument(arg) => {
+                tagged(serializer, "InvalidResolutionArgument", Some(arg))
+            }
+            Error::InvalidResolutionRange(start, end) => {
+                tagged(serializer, "InvalidResolutionRange", Some((start, end)))
+            }
+            Error::InvalidZoom(zoom) => tagged(serializer, "InvalidZoom", Some(zoom)),
+            Error::InvalidTile(z, x, y) => tagged(serializer, "InvalidTile", Some((z, x, y))),
+            Error::UnableToComputeH3Line(left, right) => {
+                tagged(serializer, "UnableToComputeH3Line", Some((left, right)))
+            }
+            Error::UnableToComputeTraversal(index, k) => {
+                tagged(serializer, "UnableToComputeTraversal", Some((index, k)))
+            }
+            Error::UnableToCompact(indices) => tagged(serializer, "UnableToCompact", Some(indices)),
+            Error::UnableToDissolve(indices) => {
+                tagged(serializer, "UnableToDissolve", Some(indices))
+            }
+            Error::InvalidPolygon(polygon) => tagged(serializer, "InvalidPolygon", Some(polygon)),
+            Error::InvalidRing(ring) => tagged(serializer, "InvalidRing", Some(ring)),
+            Error::IoError(kind) => tagged(serializer, "IoError", Some(format!("{:?}", kind))),
+            Error::GeoJsonError(error) => {
+                tagged(serializer, "GeoJsonError", Some(error.to_string()))
+            }
+        }
     }
 }
 
@@ -80,3 +207,202 @@ impl From<std::num::ParseIntError> for Error {
         Error::ParseIntError(err)
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IoError(err.kind())
+    }
+}
+
+impl From<geojson::Error> for Error {
+    fn from(err: geojson::Error) -> Self {
+        Error::GeoJsonError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::GridResolution;
+    use geo_types::line_string;
+    #[cfg(feature = "serde")]
+    use geo_types::polygon;
+    #[cfg(feature = "serde")]
+    use serde_json::json;
+
+    use crate::alloc_counter::ALLOCATIONS;
+    use std::fmt::Write;
+
+    /// A fixed-capacity sink, so that writing to it never allocates.
+    struct Sink {
+        buf: [u8; 256],
+        len: usize,
+    }
+
+    impl Write for Sink {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            let end = self.len + s.len();
+            if end > self.buf.len() {
+                return Err(std::fmt::Error);
+            }
+            self.buf[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_display_without_allocation() {
+        let cases = vec![
+            (
+                Error::IncompatibleIndices(H3Index(1), H3Index(2)),
+                "Incompatible H3 indices: H3Index=1 and H3Index=2",
+            ),
+            (
+                Error::UnableToIndex(Point::new(-122., 37.), GridResolution::Z7),
+                "Unable to index point (lat=37, lon=-122) res=Z7",
+            ),
+            (Error::UnableToDecode, "Unable to decode indexes"),
+            (Error::InvalidTile(2, 4, 0), "Invalid tile z=2 x=4 y=0"),
+            (
+                Error::InvalidResolutionRange(GridResolution::Z9, GridResolution::Z5),
+                "Invalid resolution range start=Z9 end=Z5",
+            ),
+            (
+                Error::InvalidLine(3, "zzz".to_string()),
+                "Invalid index on line 3: zzz",
+            ),
+            (
+                Error::UnableToCompact(vec![]),
+                "Unable to compact/uncompact set",
+            ),
+            (
+                Error::InvalidRing(line_string![(x: 0., y: 0.)]),
+                "Invalid ring with 1 coords",
+            ),
+        ];
+        for (error, expected) in cases {
+            let mut sink = Sink {
+                buf: [0; 256],
+                len: 0,
+            };
+            let before = ALLOCATIONS.with(|n| n.get());
+            write!(sink, "{}", error).unwrap();
+            assert_eq!(ALLOCATIONS.with(|n| n.get()), before);
+            assert_eq!(
+                std::str::from_utf8(&sink.buf[..sink.len]).unwrap(),
+                expected
+            );
+            assert_eq!(error.to_string(), expected);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_error() {
+        let index = H3Index(0x8928308280fffff);
+        let point = Point::new(-122.0, 37.0);
+        let ring = line_string![(x: 0., y: 0.), (x: 1., y: 1.)];
+        let polygon = polygon![(x: 0., y: 0.), (x: 1., y: 1.)];
+        let cases = vec![
+            (
+                Error::InvalidIndexArgument(123),
+                "InvalidIndexArgument",
+                json!(123),
+            ),
+            (
+                Error::InvalidIndexString("zzz".to_string()),
+                "InvalidIndexString",
+                json!("zzz"),
+            ),
+            (
+                Error::IncompatibleIndices(index.clone(), index.clone()),
+                "IncompatibleIndices",
+                json!([index, index]),
+            ),
+            (
+                Error::UnableToIndex(point, GridResolution::Z7),
+                "UnableToIndex",
+                json!([point, GridResolution::Z7]),
+            ),
+            (
+                Error::UnableToSerialize(index.clone()),
+                "UnableToSerialize",
+                json!(index),
+            ),
+            (Error::UnableToDecode, "UnableToDecode", json!(null)),
+            (
+                Error::InvalidPathString("20/7".to_string()),
+                "InvalidPathString",
+                json!("20/7"),
+            ),
+            (
+                Error::InvalidLine(3, "zzz".to_string()),
+                "InvalidLine",
+                json!([3, "zzz"]),
+            ),
+            (
+                Error::ParseIntError("z".parse::<i32>().unwrap_err()),
+                "ParseIntError",
+                json!("invalid digit found in string"),
+            ),
+            (
+                Error::InvalidResolutionArgument(16),
+                "InvalidResolutionArgument",
+                json!(16),
+            ),
+            (
+                Error::InvalidResolutionRange(GridResolution::Z9, GridResolution::Z5),
+                "InvalidResolutionRange",
+                json!([GridResolution::Z9, GridResolution::Z5]),
+            ),
+            (Error::InvalidZoom(31), "InvalidZoom", json!(31)),
+            (Error::InvalidTile(2, 4, 0), "InvalidTile", json!([2, 4, 0])),
+            (
+                Error::UnableToComputeH3Line(index.clone(), index.clone()),
+                "UnableToComputeH3Line",
+                json!([index, index]),
+            ),
+            (
+                Error::UnableToComputeTraversal(index.clone(), 2),
+                "UnableToComputeTraversal",
+                json!([index, 2]),
+            ),
+            (
+                Error::UnableToCompact(vec![index.clone()]),
+                "UnableToCompact",
+                json!([index]),
+            ),
+            (
+                Error::UnableToDissolve(vec![index.clone()]),
+                "UnableToDissolve",
+                json!([index]),
+            ),
+            (
+                Error::InvalidPolygon(polygon.clone()),
+                "InvalidPolygon",
+                json!(polygon),
+            ),
+            (Error::InvalidRing(ring.clone()), "InvalidRing", json!(ring)),
+            (
+                Error::IoError(std::io::ErrorKind::NotFound),
+                "IoError",
+                json!("NotFound"),
+            ),
+            (
+                Error::GeoJsonError(geojson::Error::ExpectedProperty("type".to_string())),
+                "GeoJsonError",
+                json!(geojson::Error::ExpectedProperty("type".to_string()).to_string()),
+            ),
+        ];
+        for (error, tag, value) in cases {
+            let actual = serde_json::to_value(&error).unwrap();
+            assert_eq!(actual["type"], json!(tag));
+            assert_eq!(actual["value"], value);
+        }
+        assert_eq!(
+            serde_json::to_string(&Error::InvalidIndexArgument(123)).unwrap(),
+            r#"{"type":"InvalidIndexArgument","value":123}"#
+        );
+    }
+}
@@ -19,11 +19,13 @@
 //! functions produce parent (coarser) or children (finer) cells.
 
 use crate::errors::*;
+use crate::raw::fill_buffer;
 use crate::region::*;
 use crate::resolution::*;
 use crate::types::*;
 
 use geo_types::Polygon;
+use std::collections::{HashMap, HashSet};
 
 impl H3Index {
     /// Returns the parent (or grandparent, etc) hexagon of the given hexagon
@@ -31,78 +33,310 @@ impl H3Index {
         unsafe { H3Index(h3_sys::h3ToParent(self.0, res as i32)) }
     }
 
+    /// Returns the ancestor the given number of resolutions coarser than this
+    /// index. Returns error if that would step past Z0.
+    pub fn coarsen(&self, steps: u8) -> Result<H3Index> {
+        let res = self.resolution_offset(-i32::from(steps))?;
+        Ok(self.parent(res))
+    }
+
+    /// Returns the center descendant the given number of resolutions finer
+    /// than this index. Returns error if that would step past Z15.
+    pub fn refine_center(&self, steps: u8) -> Result<H3Index> {
+        let res = self.resolution_offset(i32::from(steps))?;
+        Ok(self.center_child(res))
+    }
+
+    /// Returns the resolution offset from this index's resolution.
+    fn resolution_offset(&self, offset: i32) -> Result<GridResolution> {
+        let res = self
+            .resolution()
+            .ok_or(Error::InvalidIndexArgument(self.0))? as i32
+            + offset;
+        GridResolution::from_i32(res).ok_or(Error::InvalidResolutionArgument(res))
+    }
+
+    /// Is the given index equal to, or a descendant of, this index?
+    pub fn contains_cell(&self, other: &H3Index) -> bool {
+        match (self.resolution(), other.resolution()) {
+            (Some(res), Some(other_res)) if res <= other_res => other.parent(res) == *self,
+            _ => false,
+        }
+    }
+
+    /// Returns the finest cell containing both this index and the other, i.e.,
+    /// their nearest common ancestor. In the worst case this is a res-0 base
+    /// cell. Returns None if the indexes don't share a base cell, since no
+    /// cell contains both.
+    pub fn bounding_cell(&self, other: &H3Index) -> Option<H3Index> {
+        let finest = self
+            .resolution()
+            .min(other.resolution())
+            .unwrap_or(GridResolution::Z0);
+        (0..=finest as i32)
+            .rev()
+            .filter_map(GridResolution::from_i32)
+            .map(|res| self.parent(res))
+            .find(|ancestor| ancestor.contains_cell(other))
+    }
+
     /// Returns the maximum number of children (or grandchildren, etc) that
     /// could be for a given H3Index
     pub fn max_children(&self, child_res: GridResolution) -> usize {
         unsafe { h3_sys::maxH3ToChildrenSize(self.0, child_res as i32) as usize }
     }
 
+    /// Returns the center child (or grandchild, etc) for a given H3Index,
+    /// without enumerating the other children. Returns `H3Index(0)` if the
+    /// resolution is coarser than the index's.
+    pub fn center_child(&self, child_res: GridResolution) -> H3Index {
+        unsafe { H3Index(h3_sys::h3ToCenterChild(self.0, child_res as i32)) }
+    }
+
     /// Returns the children for a given H3Index
     pub fn children(&self, child_res: GridResolution) -> Vec<H3Index> {
         let num_children = self.max_children(child_res);
-        let mut buf = Vec::<H3Index>::with_capacity(num_children);
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            h3_sys::h3ToChildren(self.0, child_res as i32, ptr as *mut h3_sys::H3Index);
-            Vec::from_raw_parts(ptr, num_children, num_children)
+        // Pentagons have fewer children than the maximum; h3 leaves the unused
+        // slots untouched, so they must be initialized.
+        fill_buffer(num_children, |buf: *mut H3Index| unsafe {
+            h3_sys::h3ToChildren(self.0, child_res as i32, buf as *mut h3_sys::H3Index);
+        })
+    }
+
+    /// Returns an iterator over the children for a given H3Index, in the
+    /// order of `children_ordered`. Children are produced by descending one
+    /// resolution at a time, so memory use is bounded by the depth rather
+    /// than the number of children. Yields nothing if the index is finer than
+    /// the resolution.
+    pub fn children_iter(&self, child_res: GridResolution) -> impl Iterator<Item = H3Index> {
+        let mut stack = match self.resolution() {
+            Some(res) if res <= child_res => vec![self.clone()],
+            _ => vec![],
+        };
+        std::iter::from_fn(move || {
+            while let Some(cell) = stack.pop() {
+                match cell.resolution() {
+                    Some(res) if res < child_res => {
+                        let res = GridResolution::from_i32(res as i32 + 1).unwrap();
+                        stack.extend(cell.children_ordered(res).into_iter().rev());
+                    }
+                    _ => return Some(cell),
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns the children for a given H3Index, ordered by their index
+    /// digits. Since digits are stored most-significant first, this ordering
+    /// is consistent across the hierarchy, making recursive subdivision
+    /// traverse cells in a stable order.
+    pub fn children_ordered(&self, child_res: GridResolution) -> Vec<H3Index> {
+        let mut children: Vec<H3Index> = self
+            .children(child_res)
+            .into_iter()
+            .filter(|i| *i != H3Index(0))
+            .collect();
+        children.sort();
+        children
+    }
+
+    /// Subdivides the index, replacing each cell with its children while the
+    /// predicate holds for it and it's coarser than max_res, and returns the
+    /// resulting leaf cells in hierarchical order. The result covers this
+    /// index with cells of varying resolution.
+    pub fn subdivide_while<F: FnMut(&H3Index) -> bool>(
+        &self,
+        max_res: GridResolution,
+        mut should_refine: F,
+    ) -> Vec<H3Index> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![self.clone()];
+        while let Some(cell) = stack.pop() {
+            let child_res = cell
+                .resolution()
+                .filter(|res| *res < max_res)
+                .and_then(|res| GridResolution::from_i32(res as i32 + 1));
+            match child_res {
+                Some(res) if should_refine(&cell) => {
+                    stack.extend(cell.children_ordered(res).into_iter().rev());
+                }
+                _ => leaves.push(cell),
+            }
         }
+        leaves
     }
 }
 
-/// Returns the size of the array needed by uncompact.
-fn uncompact_size(set: &Vec<H3Index>, res: GridResolution) -> usize {
-    unsafe {
+/// Returns the size of the array needed by uncompact. Returns error if an
+/// index is finer than the resolution.
+fn uncompact_size(set: &[H3Index], res: GridResolution) -> Result<usize> {
+    let size = unsafe {
         h3_sys::maxUncompactSize(
             set.as_ptr() as *const h3_sys::H3Index,
             set.len() as i32,
             res as i32,
-        ) as usize
+        )
+    };
+    if size < 0 {
+        Err(Error::UnableToCompact(set.to_vec()))
+    } else {
+        Ok(size as usize)
+    }
+}
+
+/// Is the set of indexes compacted, i.e., does no index contain another?
+/// Indexes may be of mixed resolution.
+pub fn is_compacted(cells: &[H3Index]) -> bool {
+    let mut seen = HashSet::new();
+    if !cells.iter().all(|cell| seen.insert(cell)) {
+        return false;
     }
+    cells.iter().all(|cell| {
+        let res = cell.resolution().map_or(0, |res| res as i32);
+        (0..res)
+            .filter_map(GridResolution::from_i32)
+            .all(|parent_res| !seen.contains(&cell.parent(parent_res)))
+    })
 }
 
 pub trait ToCompactH3Region {
-    /// Compacts the set indexes as best as possible.
+    /// Compacts the set indexes as best as possible, replacing each complete
+    /// group of children with their parent, recursively. The indexes must
+    /// share a single resolution (see `compact_checked`), and the result is
+    /// of mixed resolution. Returns error if h3 can't compact the set, e.g.,
+    /// if it contains duplicates. `uncompact` reverses this.
     fn compact(&self) -> Result<Vec<H3Index>>;
 }
 
-/// Uncompacts the set of indexes to the resolution
-fn uncompact(set: &Vec<H3Index>, res: GridResolution) -> Result<Vec<H3Index>> {
-    let max_size = uncompact_size(&set, res);
-    let mut buf = Vec::<H3Index>::with_capacity(max_size);
-    let ptr = buf.as_mut_ptr();
-    unsafe {
-        let err = h3_sys::uncompact(
-            set.as_ptr() as *const h3_sys::H3Index,
-            set.len() as i32,
-            ptr as *mut h3_sys::H3Index,
-            max_size as i32,
-            res as i32,
-        );
-        if err == 0 {
-            Ok(Vec::from_raw_parts(ptr, max_size, max_size))
-        } else {
-            Err(Error::UnableToCompact(set.clone()))
+/// Compacts the set of indexes, first checking that they share a single
+/// resolution, as h3 requires. Returns `IncompatibleIndices` with the first
+/// index and the first index at a different resolution otherwise; uncompact
+/// mixed-resolution sets before compacting them.
+pub fn compact_checked(cells: &[H3Index]) -> Result<Vec<H3Index>> {
+    let mut nonzero = cells.iter().filter(|i| **i != H3Index(0));
+    if let Some(first) = nonzero.next() {
+        let res = first.resolution();
+        if let Some(other) = nonzero.find(|i| i.resolution() != res) {
+            return Err(Error::IncompatibleIndices(first.clone(), other.clone()));
+        }
+    }
+    cells.to_vec().compact()
+}
+
+/// Merges every complete group of siblings in the set into their parent,
+/// repeating until no complete group remains, and returns the sorted result.
+/// Unlike `compact`, indexes may be of mixed resolution, and incomplete
+/// groups are left at their own resolution.
+pub fn merge_complete_parents(cells: &[H3Index]) -> Vec<H3Index> {
+    let mut set: HashSet<H3Index> = cells
+        .iter()
+        .filter(|i| **i != H3Index(0))
+        .cloned()
+        .collect();
+    loop {
+        let mut groups: HashMap<H3Index, Vec<H3Index>> = HashMap::new();
+        for cell in &set {
+            let parent_res = cell
+                .resolution()
+                .and_then(|res| GridResolution::from_i32(res as i32 - 1));
+            if let Some(parent_res) = parent_res {
+                groups
+                    .entry(cell.parent(parent_res))
+                    .or_default()
+                    .push(cell.clone());
+            }
+        }
+        let mut merged = false;
+        for (parent, children) in groups {
+            let child_res = children[0].resolution().unwrap();
+            if children.len() == parent.children_ordered(child_res).len() {
+                for child in &children {
+                    set.remove(child);
+                }
+                set.insert(parent);
+                merged = true;
+            }
+        }
+        if !merged {
+            break;
         }
     }
+    let mut merged: Vec<H3Index> = set.into_iter().collect();
+    merged.sort();
+    merged
+}
+
+/// Uncompacts the set of indexes to the resolution, replacing each index with
+/// its descendants at that resolution. Indexes may be of mixed resolution.
+/// Returns error if an index is finer than the resolution.
+pub fn uncompact(set: &[H3Index], res: GridResolution) -> Result<Vec<H3Index>> {
+    let max_size = uncompact_size(set, res)?;
+    let mut err = 0;
+    let mut buf = fill_buffer(max_size, |buf: *mut H3Index| {
+        err = unsafe {
+            h3_sys::uncompact(
+                set.as_ptr() as *const h3_sys::H3Index,
+                set.len() as i32,
+                buf as *mut h3_sys::H3Index,
+                max_size as i32,
+                res as i32,
+            )
+        };
+    });
+    if err == 0 {
+        // Pentagons have fewer descendants than the maximum; drop unused slots.
+        buf.retain(|i| *i != H3Index(0));
+        Ok(buf)
+    } else {
+        Err(Error::UnableToCompact(set.to_vec()))
+    }
+}
+
+/// Uncompacts the set of indexes to the resolution, passing each resulting
+/// index to the callback rather than collecting them. Descends one resolution
+/// at a time, so memory use is bounded by the depth rather than the size of
+/// the expansion. Returns error, before any callbacks, if an index is finer
+/// than the resolution.
+pub fn uncompact_for_each<F: FnMut(H3Index)>(
+    set: &[H3Index],
+    res: GridResolution,
+    mut f: F,
+) -> Result<()> {
+    let set: Vec<H3Index> = set.iter().filter(|i| **i != H3Index(0)).cloned().collect();
+    if set
+        .iter()
+        .any(|i| !matches!(i.resolution(), Some(r) if r <= res))
+    {
+        return Err(Error::UnableToCompact(set));
+    }
+    for cell in set {
+        cell.children_iter(res).for_each(&mut f);
+    }
+    Ok(())
 }
 
 impl ToCompactH3Region for Vec<H3Index> {
     fn compact(&self) -> Result<Vec<H3Index>> {
-        let mut buf = Vec::<H3Index>::with_capacity(self.len());
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            let err = h3_sys::compact(
-                self.as_ptr() as *const h3_sys::H3Index,
-                ptr as *mut h3_sys::H3Index,
-                self.len() as i32,
-            );
-            if err == 0 {
-                Ok(Vec::from_raw_parts(ptr, self.len(), self.len()))
-            } else {
-                Err(Error::UnableToCompact(self.clone()))
-            }
+        // h3 can't compact a set containing empty (zero) entries, such as the
+        // padding in polyfill results.
+        let set: Vec<H3Index> = self.iter().filter(|i| **i != H3Index(0)).cloned().collect();
+        let mut err = 0;
+        let mut buf = fill_buffer(set.len(), |buf: *mut H3Index| {
+            err = unsafe {
+                h3_sys::compact(
+                    set.as_ptr() as *const h3_sys::H3Index,
+                    buf as *mut h3_sys::H3Index,
+                    set.len() as i32,
+                )
+            };
+        });
+        if err == 0 {
+            // The compacted set is shorter than the input; drop unused slots.
+            buf.retain(|i| *i != H3Index(0));
+            Ok(buf)
+        } else {
+            Err(Error::UnableToCompact(self.clone()))
         }
     }
 }
@@ -128,6 +362,173 @@ mod tests {
         assert_eq!(z8_children.len(), 7);
     }
 
+    #[test]
+    fn test_index_children_ordered() {
+        let index = H3Index(0x87283472bffffff);
+        let children = index.children_ordered(GridResolution::Z9);
+        assert_eq!(children.len(), 49);
+        assert_eq!(children, index.children_ordered(GridResolution::Z9));
+        for pair in children.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+        for child in index.children(GridResolution::Z9) {
+            assert!(children.contains(&child));
+        }
+        // Pentagons have a deleted subsequence, which is omitted.
+        let pentagon = H3Index(0x821c07fffffffff);
+        assert_eq!(pentagon.children_ordered(GridResolution::Z3).len(), 6);
+    }
+
+    #[test]
+    fn test_children_iter() {
+        let index = H3Index(0x87283472bffffff);
+        let res = GridResolution::Z9;
+        let children: Vec<H3Index> = index.children_iter(res).collect();
+        assert_eq!(children.len(), 49);
+        assert_eq!(children, index.children_ordered(res));
+        let mut eager: Vec<H3Index> = index.children(res);
+        eager.sort();
+        assert_eq!(children, eager);
+        assert_eq!(
+            index.children_iter(GridResolution::Z7).collect::<Vec<_>>(),
+            vec![index.clone()]
+        );
+        assert_eq!(index.children_iter(GridResolution::Z6).count(), 0);
+        let pentagon = H3Index(0x821c07fffffffff);
+        assert_eq!(
+            pentagon
+                .children_iter(GridResolution::Z4)
+                .collect::<Vec<_>>(),
+            pentagon.children_ordered(GridResolution::Z4)
+        );
+    }
+
+    #[test]
+    fn test_center_child() {
+        let index = H3Index(0x87283472bffffff);
+        for res in [GridResolution::Z7, GridResolution::Z8, GridResolution::Z12] {
+            let center = index.center_child(res);
+            assert_eq!(center.resolution(), Some(res));
+            assert_eq!(center.parent(GridResolution::Z7), index);
+            assert_eq!(center, index.children_ordered(res)[0]);
+        }
+        assert_eq!(index.center_child(GridResolution::Z6), H3Index(0));
+        let pentagon = H3Index(0x821c07fffffffff);
+        assert!(pentagon.center_child(GridResolution::Z5).is_pentagon());
+    }
+
+    #[test]
+    fn test_subdivide_while() {
+        let target = H3Index(0x8928308280fffff);
+        let parent = target.parent(GridResolution::Z7);
+        let leaves = parent.subdivide_while(GridResolution::Z9, |cell| cell.contains_cell(&target));
+        // Each refinement replaces the cell containing the target with its
+        // seven children.
+        assert_eq!(leaves.len(), 1 + 6 + 6);
+        assert!(leaves.contains(&target));
+        let mut resolutions: Vec<_> = leaves.iter().filter_map(|c| c.resolution()).collect();
+        resolutions.sort();
+        resolutions.dedup();
+        assert_eq!(resolutions, vec![GridResolution::Z8, GridResolution::Z9]);
+        assert!(is_compacted(&leaves));
+        assert_eq!(uncompact(&leaves, GridResolution::Z9).unwrap().len(), 49);
+        assert_eq!(
+            parent.subdivide_while(GridResolution::Z7, |_| true),
+            vec![parent.clone()]
+        );
+    }
+
+    #[test]
+    fn test_coarsen_and_refine_center() {
+        let index = H3Index(0x87283472bffffff);
+        assert_eq!(index.coarsen(0), Ok(index.clone()));
+        assert_eq!(index.coarsen(2), Ok(index.parent(GridResolution::Z5)));
+        assert_eq!(index.coarsen(8), Err(Error::InvalidResolutionArgument(-1)));
+        let child = index.refine_center(2).unwrap();
+        assert_eq!(child.resolution(), Some(GridResolution::Z9));
+        assert_eq!(child.coarsen(2), Ok(index.clone()));
+        assert_eq!(
+            index.refine_center(9),
+            Err(Error::InvalidResolutionArgument(16))
+        );
+    }
+
+    #[test]
+    fn test_bounding_cell() {
+        let parent = H3Index(0x8828308281fffff);
+        let children = parent.children_ordered(GridResolution::Z9);
+        assert_eq!(
+            children[1].bounding_cell(&children[2]),
+            Some(parent.clone())
+        );
+        assert_eq!(children[1].bounding_cell(&parent), Some(parent.clone()));
+        assert_eq!(
+            children[1].bounding_cell(&children[1]),
+            Some(children[1].clone())
+        );
+        let grandchild = children[3].refine_center(2).unwrap();
+        assert_eq!(children[1].bounding_cell(&grandchild), Some(parent));
+        let index = H3Index(0x8928308280fffff);
+        let other_base_cell = H3Index(0x891ea6d6533ffff);
+        assert_ne!(index.base_cell(), other_base_cell.base_cell());
+        assert_eq!(index.bounding_cell(&other_base_cell), None);
+        assert_eq!(other_base_cell.bounding_cell(&index), None);
+    }
+
+    #[test]
+    fn test_compact_checked() {
+        let parent = H3Index(0x8828308281fffff);
+        let children = parent.children(GridResolution::Z9);
+        assert_eq!(compact_checked(&children), Ok(vec![parent.clone()]));
+        let mut mixed = children.clone();
+        mixed.push(parent.k_ring_indices(1)[1].clone());
+        assert_eq!(
+            compact_checked(&mixed),
+            Err(Error::IncompatibleIndices(
+                children[0].clone(),
+                mixed[mixed.len() - 1].clone()
+            ))
+        );
+        assert_eq!(compact_checked(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_is_compacted() {
+        let parent = H3Index(0x87283472bffffff);
+        let sibling = parent.k_ring_indices(1)[1].clone();
+        assert!(parent.contains_cell(&parent.children(GridResolution::Z9)[10]));
+        assert!(!parent.contains_cell(&sibling.children(GridResolution::Z9)[10]));
+        let mut cells = vec![parent.clone()];
+        cells.extend(sibling.children(GridResolution::Z8));
+        assert!(is_compacted(&cells));
+        cells.push(parent.children(GridResolution::Z9)[0].clone());
+        assert!(!is_compacted(&cells));
+        assert!(!is_compacted(&[parent.clone(), parent]));
+    }
+
+    #[test]
+    fn test_merge_complete_parents() {
+        let parent = H3Index(0x87283472bffffff);
+        let sibling = parent.k_ring_indices(1)[1].clone();
+        let complete = parent.children_ordered(GridResolution::Z8);
+        let partial = sibling.children_ordered(GridResolution::Z8)[1..].to_vec();
+        let mut cells = complete.clone();
+        cells.extend(partial.clone());
+        let mut expected = partial;
+        expected.push(parent.clone());
+        expected.sort();
+        assert_eq!(merge_complete_parents(&cells), expected);
+
+        // Merging cascades through resolutions, and handles pentagons.
+        let grandchildren = parent.children_ordered(GridResolution::Z9);
+        assert_eq!(merge_complete_parents(&grandchildren), vec![parent]);
+        let pentagon = H3Index(0x821c07fffffffff);
+        let children = pentagon.children_ordered(GridResolution::Z3);
+        assert_eq!(children.len(), 6);
+        assert_eq!(merge_complete_parents(&children), vec![pentagon]);
+        assert_eq!(merge_complete_parents(&[]), vec![]);
+    }
+
     #[test]
     fn test_compact_and_uncompact() {
         let poly = polygon!(
@@ -146,5 +547,43 @@ mod tests {
         assert_eq!(compact_hexes.len(), 209);
         let uncompact_hexes = uncompact(&compact_hexes, res).unwrap();
         assert_eq!(uncompact_hexes.len(), 1253);
+        let pentagon = H3Index(0x821c07fffffffff);
+        assert_eq!(
+            uncompact(std::slice::from_ref(&pentagon), GridResolution::Z4).unwrap(),
+            pentagon
+                .children(GridResolution::Z4)
+                .into_iter()
+                .filter(|i| *i != H3Index(0))
+                .collect::<Vec<_>>()
+        );
+        assert!(uncompact(&uncompact_hexes, GridResolution::Z8).is_err());
+    }
+
+    #[test]
+    fn test_uncompact_for_each() {
+        let origin = H3Index(0x8928308280fffff);
+        let compacted = origin.k_ring_indices(6).compact().unwrap();
+        let res = GridResolution::Z11;
+        let expected: Vec<H3Index> = uncompact(&compacted, res)
+            .unwrap()
+            .into_iter()
+            .filter(|i| *i != H3Index(0))
+            .collect();
+        let mut count = 0;
+        uncompact_for_each(&compacted, res, |cell| {
+            assert_eq!(cell.resolution(), Some(res));
+            count += 1;
+        })
+        .unwrap();
+        assert_eq!(count, expected.len());
+
+        let pentagon = H3Index(0x821c07fffffffff);
+        let mut cells = Vec::new();
+        uncompact_for_each(std::slice::from_ref(&pentagon), GridResolution::Z4, |c| {
+            cells.push(c)
+        })
+        .unwrap();
+        assert_eq!(cells, pentagon.children_ordered(GridResolution::Z4));
+        assert!(uncompact_for_each(&compacted, GridResolution::Z8, |_| ()).is_err());
     }
 }
@@ -24,13 +24,69 @@ use crate::resolution::*;
 use crate::types::*;
 
 /// A unique hierarchical index for an H3 cell
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+///
+/// The index is guaranteed to have the same layout as the raw
+/// `h3_sys::H3Index`, so a slice of indexes can be passed to h3 as a
+/// `*const h3_sys::H3Index` without copying.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(transparent)]
 pub struct H3Index(pub h3_sys::H3Index);
 
+// Buffers of indexes are passed to h3 by pointer cast, which relies on this.
+const _: () = assert!(
+    std::mem::size_of::<H3Index>() == std::mem::size_of::<h3_sys::H3Index>()
+        && std::mem::align_of::<H3Index>() == std::mem::align_of::<h3_sys::H3Index>()
+);
+
+/// Serializes as the canonical hex string, e.g., "8928308280fffff".
+#[cfg(feature = "serde")]
+impl serde::Serialize for H3Index {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let s: Result<String> = self.clone().into();
+        serializer.serialize_str(&s.map_err(serde::ser::Error::custom)?)
+    }
+}
+
+/// Deserializes from the canonical hex string, rejecting invalid indexes.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for H3Index {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Constructs a validated H3Index from a hex string literal, panicking if the
+/// literal isn't a valid index. Intended for tests and constants, e.g.,
+/// `h3!("8928308280fffff")`.
+#[macro_export]
+macro_rules! h3 {
+    ($hex:expr) => {
+        $crate::H3Index::new(u64::from_str_radix($hex, 16).expect("Invalid hex literal!"))
+            .expect("Invalid H3 index literal!")
+    };
+}
+
 pub trait ToH3Index {
     /// Indexes the location at the specified resolution, returning the index of
     /// the cell containing the location.
     fn to_h3_index(&self, res: GridResolution) -> Result<H3Index>;
+
+    /// Indexes the location as in `to_h3_index`, but if indexing a finite
+    /// location fails, retries with the location nudged by a tiny fraction
+    /// of the cell size. This only masks failures from floating-point edge
+    /// cases; non-finite locations are still errors.
+    fn to_h3_index_or_nearest(&self, res: GridResolution) -> Result<H3Index> {
+        match self.to_h3_index(res) {
+            Err(Error::UnableToIndex(p, res)) => index_or_nearest(p, res, |p| p.to_h3_index(res)),
+            result => result,
+        }
+    }
 }
 
 impl H3Index {
@@ -51,6 +107,97 @@ impl H3Index {
         }
         GeoCoord(c).into()
     }
+
+    /// Exact area of the cell in square radians, i.e., on the unit sphere.
+    pub fn area_rads2(&self) -> f64 {
+        unsafe { h3_sys::cellAreaRads2(self.0) }
+    }
+
+    /// Exact area of the cell in square kilometers.
+    pub fn area_km2(&self) -> f64 {
+        unsafe { h3_sys::cellAreaKm2(self.0) }
+    }
+
+    /// Exact area of the cell in square meters.
+    pub fn area_m2(&self) -> f64 {
+        unsafe { h3_sys::cellAreaM2(self.0) }
+    }
+
+    /// Returns the centroid as a 3D Cartesian (ECEF) vector on the unit
+    /// sphere.
+    pub fn to_cartesian(&self) -> [f64; 3] {
+        point_to_unit_vector(self.centroid())
+    }
+
+    /// Returns the cell at the same resolution containing the point antipodal
+    /// to this cell's centroid.
+    pub fn antipode(&self) -> Result<H3Index> {
+        let res = match self.resolution() {
+            Some(res) if self.is_valid() => res,
+            _ => return Err(Error::InvalidIndexArgument(self.0)),
+        };
+        let v = self.to_cartesian();
+        unit_vector_to_point([-v[0], -v[1], -v[2]]).to_h3_index(res)
+    }
+
+    /// Returns the point at fraction t along the great-circle arc between the
+    /// centroids of this index and another.
+    pub fn interpolate_centroid(&self, other: &H3Index, t: f64) -> Point<f64> {
+        let a = self.to_cartesian();
+        let b = other.to_cartesian();
+        let dot = (a[0] * b[0] + a[1] * b[1] + a[2] * b[2]).clamp(-1., 1.);
+        let omega = dot.acos();
+        if omega.abs() < f64::EPSILON {
+            return self.centroid();
+        }
+        let wa = ((1. - t) * omega).sin() / omega.sin();
+        let wb = (t * omega).sin() / omega.sin();
+        unit_vector_to_point([
+            wa * a[0] + wb * b[0],
+            wa * a[1] + wb * b[1],
+            wa * a[2] + wb * b[2],
+        ])
+    }
+}
+
+/// Returns the 122 resolution 0 indexes, i.e., the base cells.
+pub fn res0_indexes() -> Vec<H3Index> {
+    let count = unsafe { h3_sys::res0IndexCount() } as usize;
+    fill_buffer(count, |buf: *mut H3Index| unsafe {
+        h3_sys::getRes0Indexes(buf as *mut h3_sys::H3Index);
+    })
+}
+
+/// Converts a lat/lon point (degrees) into a 3D unit vector.
+pub(crate) fn point_to_unit_vector(p: Point<f64>) -> [f64; 3] {
+    let (lat, lng) = (p.lat().to_radians(), p.lng().to_radians());
+    [lat.cos() * lng.cos(), lat.cos() * lng.sin(), lat.sin()]
+}
+
+/// Converts a 3D vector into a lat/lon point (degrees).
+pub(crate) fn unit_vector_to_point(v: [f64; 3]) -> Point<f64> {
+    let lat = v[2].atan2((v[0] * v[0] + v[1] * v[1]).sqrt());
+    let lng = v[1].atan2(v[0]);
+    Point::new(lng.to_degrees(), lat.to_degrees())
+}
+
+/// Indexes the point, retrying at nudged locations if indexing fails.
+fn index_or_nearest<F>(p: Point<f64>, res: GridResolution, index: F) -> Result<H3Index>
+where
+    F: Fn(Point<f64>) -> Result<H3Index>,
+{
+    let err = match index(p) {
+        Ok(i) => return Ok(i),
+        Err(err) => err,
+    };
+    if !p.lng().is_finite() || !p.lat().is_finite() {
+        return Err(err);
+    }
+    let eps = res.coordinate_tolerance();
+    [(eps, 0.), (-eps, 0.), (0., eps), (0., -eps)]
+        .iter()
+        .find_map(|(dx, dy)| index(Point::new(p.lng() + dx, p.lat() + dy)).ok())
+        .ok_or(err)
 }
 
 impl ToH3Index for Point<f64> {
@@ -58,13 +205,42 @@ impl ToH3Index for Point<f64> {
         let c = GeoCoord::from(*self).0;
         let index = unsafe { h3_sys::geoToH3(&c, res as i32) };
         if index == 0 {
-            Err(Error::UnableToIndex(*self))
+            Err(Error::UnableToIndex(*self, res))
         } else {
             H3Index::new(index)
         }
     }
 }
 
+/// A location in degrees, with explicitly named latitude and longitude to
+/// avoid confusing the (x=lng, y=lat) ordering of `Point`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LatLng {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl LatLng {
+    /// Converts to a Point, with x=lng and y=lat.
+    pub fn to_point(self) -> Point<f64> {
+        Point::new(self.lng, self.lat)
+    }
+
+    /// Converts from a Point, with x=lng and y=lat.
+    pub fn from_point(p: Point<f64>) -> Self {
+        LatLng {
+            lat: p.lat(),
+            lng: p.lng(),
+        }
+    }
+}
+
+impl ToH3Index for LatLng {
+    fn to_h3_index(&self, res: GridResolution) -> Result<H3Index> {
+        self.to_point().to_h3_index(res)
+    }
+}
+
 impl From<H3Index> for Point<f64> {
     /// Finds the centroid of the index.
     fn from(i: H3Index) -> Point<f64> {
@@ -79,9 +255,28 @@ impl From<H3Index> for Point<f64> {
 impl std::str::FromStr for H3Index {
     type Err = Error;
 
+    /// Parses the canonical hex string (e.g., "8928308280fffff"), falling
+    /// back to the decimal value (e.g., "617700169958293503").
+    ///
+    /// Hex takes precedence. This is unambiguous: a valid index always has a
+    /// letter in its hex form, since from Z10 the resolution is a hex digit
+    /// `a` through `f`, and below Z10 the unused digits form a run of `f`s. So
+    /// an all-digit string is never a valid hex index.
+    /// Returns `InvalidIndexArgument` if the string is a decimal value but not
+    /// a valid index, and `InvalidIndexString` if it's neither hex nor
+    /// decimal.
     fn from_str(s: &str) -> Result<Self> {
-        let idx_val = s.parse::<u64>()?;
-        H3Index::new(idx_val)
+        let hex = match std::ffi::CString::new(s) {
+            Ok(terminated) => unsafe { h3_sys::stringToH3(terminated.as_ptr()) },
+            Err(_) => 0,
+        };
+        if H3Index(hex).is_valid() {
+            return Ok(H3Index(hex));
+        }
+        match s.parse::<u64>() {
+            Ok(value) => H3Index::new(value),
+            Err(_) => Err(Error::InvalidIndexString(s.to_string())),
+        }
     }
 }
 
@@ -89,13 +284,10 @@ impl std::str::FromStr for H3Index {
 mod tests {
     use super::*;
 
-    use num_traits::FromPrimitive;
-
     #[test]
     fn test_h3_is_valid() {
         // H3 Address is considered an address
-        for i in 0..MAX_GRID_RESOLUTION + 1 {
-            let res = GridResolution::from_i32(i).expect("GridResolution failed!");
+        for res in GridResolution::iter() {
             assert!(Point::new(-122., 37.).to_h3_index(res).is_ok());
         }
         // Added!
@@ -107,6 +299,203 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn test_unable_to_index_resolution() {
+        let point = Point::new(f64::NAN, 0.);
+        match point.to_h3_index(GridResolution::Z7) {
+            Err(Error::UnableToIndex(_, res)) => assert_eq!(res, GridResolution::Z7),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_to_h3_index_or_nearest() {
+        let res = GridResolution::Z9;
+        let p = Point::new(-122.418307270836, 37.7752702151959);
+        let expected = H3Index(0x8928308280fffff);
+        assert_eq!(p.to_h3_index_or_nearest(res), Ok(expected.clone()));
+        // Fail at exactly the given point to force a retry.
+        let flaky = |q: Point<f64>| {
+            if q == p {
+                Err(Error::UnableToIndex(q, res))
+            } else {
+                q.to_h3_index(res)
+            }
+        };
+        assert_eq!(index_or_nearest(p, res, flaky), Ok(expected));
+        let failing = |q: Point<f64>| Err(Error::UnableToIndex(q, res));
+        assert_eq!(
+            index_or_nearest(p, res, failing),
+            Err(Error::UnableToIndex(p, res))
+        );
+        let nan = Point::new(f64::NAN, 0.);
+        assert!(nan.to_h3_index_or_nearest(res).is_err());
+        // Implementors only need to_h3_index; the default retries at the
+        // location reported in the error.
+        struct Flaky(Point<f64>);
+        impl ToH3Index for Flaky {
+            fn to_h3_index(&self, res: GridResolution) -> Result<H3Index> {
+                Err(Error::UnableToIndex(self.0, res))
+            }
+        }
+        assert_eq!(
+            Flaky(p).to_h3_index_or_nearest(res),
+            Ok(H3Index(0x8928308280fffff))
+        );
+        let lat_lng = LatLng::from_point(p);
+        assert_eq!(
+            lat_lng.to_h3_index_or_nearest(res),
+            Ok(H3Index(0x8928308280fffff))
+        );
+    }
+
+    #[test]
+    fn test_lat_lng() {
+        let (lat, lng) = (37.7752702151959, -122.418307270836);
+        let res = GridResolution::Z9;
+        let expected = Point::new(lng, lat).to_h3_index(res).unwrap();
+        assert_eq!(LatLng { lat, lng }.to_h3_index(res), Ok(expected.clone()));
+        assert_eq!(expected, H3Index(0x8928308280fffff));
+        let p = LatLng { lat, lng }.to_point();
+        assert_eq!(LatLng::from_point(p), LatLng { lat, lng });
+    }
+
+    #[test]
+    fn test_to_cartesian() {
+        let index = H3Index(0x8928308280fffff);
+        let v = index.to_cartesian();
+        assert_relative_eq!((v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt(), 1.);
+        let centroid = index.centroid();
+        let p = unit_vector_to_point(v);
+        let eps = GridResolution::Z9.coordinate_tolerance();
+        assert_relative_eq!(p.lat(), centroid.lat(), epsilon = eps);
+        assert_relative_eq!(p.lng(), centroid.lng(), epsilon = eps);
+    }
+
+    #[test]
+    fn test_cell_area() {
+        let index = H3Index(0x8928308280fffff);
+        assert_relative_eq!(
+            index.area_m2(),
+            index.area_km2() * 1.0e6,
+            max_relative = 1e-9
+        );
+        assert_relative_eq!(
+            index.area_m2() / index.area_rads2(),
+            METERS_PER_DEGREE.powi(2) * (180. / std::f64::consts::PI).powi(2),
+            max_relative = 1e-6
+        );
+        let avg = GridResolution::Z9.hex_area();
+        assert!(index.area_m2() > 0.5 * avg && index.area_m2() < 2. * avg);
+
+        let pentagon = H3Index(0x821c07fffffffff);
+        for neighbor in pentagon.k_ring_indices(1) {
+            if neighbor != pentagon {
+                assert!(pentagon.area_m2() < neighbor.area_m2());
+            }
+        }
+    }
+
+    #[test]
+    fn test_antipode() {
+        let index = H3Index(0x8928308280fffff);
+        let antipode = index.antipode().unwrap();
+        assert_eq!(antipode.resolution(), Some(GridResolution::Z9));
+        let c = antipode.centroid();
+        assert!(c.lat() < 0. && c.lng() > 0.);
+        let round_trip = antipode.antipode().unwrap();
+        assert!(index.k_ring_indices(1).contains(&round_trip));
+        assert!(H3Index(0).antipode().is_err());
+    }
+
+    #[test]
+    fn test_res0_indexes() {
+        let cells = res0_indexes();
+        assert_eq!(cells.len(), 122);
+        assert!(cells.iter().all(|c| c.is_valid()));
+        assert!(cells
+            .iter()
+            .all(|c| c.resolution() == Some(GridResolution::Z0)));
+        let base_cells: Vec<i32> = cells.iter().map(|c| c.base_cell()).collect();
+        assert_eq!(base_cells, (0..122).collect::<Vec<i32>>());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        for (index, hex) in [
+            (H3Index(0x8928308280fffff), "\"8928308280fffff\""),
+            (H3Index(0x821c07fffffffff), "\"821c07fffffffff\""),
+        ] {
+            let json = serde_json::to_string(&index).unwrap();
+            assert_eq!(json, hex);
+            assert_eq!(serde_json::from_str::<H3Index>(&json).unwrap(), index);
+        }
+        assert!(serde_json::from_str::<H3Index>("\"5004295803a88\"").is_err());
+        assert!(serde_json::from_str::<H3Index>("\"not hex\"").is_err());
+        assert!(serde_json::from_str::<H3Index>("617700169958293503").is_err());
+    }
+
+    #[test]
+    fn test_from_str() {
+        let index = H3Index(0x8928308280fffff);
+        assert_eq!("8928308280fffff".parse::<H3Index>(), Ok(index.clone()));
+        assert_eq!("617700169958293503".parse::<H3Index>(), Ok(index.clone()));
+        assert_eq!(
+            "821c07fffffffff".parse::<H3Index>(),
+            Ok(H3Index(0x821c07fffffffff))
+        );
+        assert_eq!(
+            "not an index".parse::<H3Index>(),
+            Err(Error::InvalidIndexString("not an index".to_string()))
+        );
+        assert_eq!(
+            "".parse::<H3Index>(),
+            Err(Error::InvalidIndexString("".to_string()))
+        );
+        assert_eq!(
+            "123".parse::<H3Index>(),
+            Err(Error::InvalidIndexArgument(123))
+        );
+        // All-digit strings that h3 accepts as hex aren't valid indexes, so
+        // they're read as decimal.
+        let digits = "617700169958293503";
+        let as_hex = unsafe {
+            let terminated = std::ffi::CString::new(digits).unwrap();
+            h3_sys::stringToH3(terminated.as_ptr())
+        };
+        assert_ne!(as_hex, 0);
+        assert!(!H3Index(as_hex).is_valid());
+        assert_eq!(digits.parse::<H3Index>(), Ok(index.clone()));
+        // Hex strings without an `f` are still detected as hex.
+        let res14 = H3Index(0x8e28308280c0007);
+        assert!(res14.is_valid());
+        assert_eq!(format!("{:x}", res14), "8e28308280c0007");
+        assert_eq!("8e28308280c0007".parse::<H3Index>(), Ok(res14));
+        assert!("8928308280fffff\0".parse::<H3Index>().is_err());
+    }
+
+    #[test]
+    fn test_layout_matches_raw_index() {
+        let cells = [H3Index(0x8928308280fffff), H3Index(0x821c07fffffffff)];
+        let raw = unsafe {
+            std::slice::from_raw_parts(cells.as_ptr() as *const h3_sys::H3Index, cells.len())
+        };
+        assert_eq!(raw, &[0x8928308280fffff, 0x821c07fffffffff]);
+    }
+
+    #[test]
+    fn test_h3_macro() {
+        assert_eq!(h3!("8928308280fffff"), H3Index(0x8928308280fffff));
+        assert!(h3!("8928308280fffff").is_valid());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_h3_macro_invalid() {
+        h3!("5004295803a88");
+    }
+
     #[test]
     fn test_geo_to_h3() {
         // geo_to_h3: Got the expected H3 address back
@@ -138,6 +527,23 @@ mod tests {
         assert_relative_eq!(actual.lng(), expected.lng(), epsilon = eps);
     }
 
+    #[test]
+    fn test_interpolate_centroid() {
+        let start = H3Index(0x85283473fffffff);
+        let end = H3Index(0x8528342bfffffff);
+        let eps = GridResolution::Z5.coordinate_tolerance();
+        assert_approx_point(start.centroid(), start.interpolate_centroid(&end, 0.), eps);
+        assert_approx_point(end.centroid(), start.interpolate_centroid(&end, 1.), eps);
+        let mid = start.interpolate_centroid(&end, 0.5);
+        let (a, b) = (start.centroid(), end.centroid());
+        assert_approx_point(
+            Point::new((a.lng() + b.lng()) / 2., (a.lat() + b.lat()) / 2.),
+            mid,
+            1.0e-2,
+        );
+        assert_approx_point(a, start.interpolate_centroid(&start, 0.5), eps);
+    }
+
     #[test]
     fn test_h3_to_geo() {
         let index = H3Index::new(0x85283473fffffff).unwrap();
@@ -148,7 +554,7 @@ mod tests {
         assert_approx_point(
             index.into(),
             Point::new(-121.97637597255124, 37.34579337536848),
-            1.0e-9,
+            GridResolution::Z5.coordinate_tolerance(),
         );
     }
 }
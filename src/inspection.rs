@@ -20,11 +20,14 @@
 //! 64-bit representation of an H3 index.
 
 use crate::errors::*;
+use crate::raw::fill_buffer_with;
 use crate::resolution::*;
 use crate::types::*;
 
-use num_traits::FromPrimitive;
-use std::ffi::CString;
+use std::ffi::CStr;
+
+/// Base cell numbers of the twelve pentagonal base cells.
+const PENTAGON_BASE_CELLS: [i32; 12] = [4, 14, 24, 38, 49, 58, 63, 72, 83, 97, 107, 117];
 
 impl H3Index {
     /// Is the given H3Index valid?
@@ -48,11 +51,94 @@ impl H3Index {
         unsafe { h3_sys::h3GetBaseCell(self.0) }
     }
 
+    /// Is the index's base cell one of the twelve pentagons? For resolution 0
+    /// indexes this is equivalent to `is_pentagon`.
+    pub fn is_base_pentagon(&self) -> bool {
+        PENTAGON_BASE_CELLS.contains(&self.base_cell())
+    }
+
+    /// Returns the base cell of the nearest pentagon, if this index lies in
+    /// a pentagon base cell or in a base cell adjacent to one (i.e., within
+    /// the pentagon's distortion influence), else None.
+    pub fn pentagon_axis(&self) -> Option<i32> {
+        let center = self.to_cartesian();
+        let angular_distance = |cell: &H3Index| {
+            let v = cell.to_cartesian();
+            -(center[0] * v[0] + center[1] * v[1] + center[2] * v[2])
+        };
+        self.parent(GridResolution::Z0)
+            .k_ring_indices(1)
+            .into_iter()
+            .filter(|cell| cell.is_pentagon())
+            .min_by(|a, b| angular_distance(a).total_cmp(&angular_distance(b)))
+            .map(|cell| cell.base_cell())
+    }
+
     /// Returns the resolution of the given H3Index
     pub fn resolution(&self) -> Option<GridResolution> {
         unsafe { GridResolution::from_i32(h3_sys::h3GetResolution(self.0)) }
     }
 
+    /// Returns a compact, human-scannable token of the form
+    /// `res{N}:bc{B}:{hex}` for log correlation.
+    pub fn to_debug_string(&self) -> String {
+        let res = unsafe { h3_sys::h3GetResolution(self.0) };
+        format!("res{}:bc{}:{:x}", res, self.base_cell(), self.0)
+    }
+
+    /// Returns the hierarchical path `{base_cell}/{digit}/{digit}/...` of the
+    /// index, with one base-7 digit per resolution down to the index's own.
+    /// A parent's path is a prefix of its descendants' paths.
+    pub fn to_path_string(&self) -> String {
+        let res = unsafe { h3_sys::h3GetResolution(self.0) };
+        let mut path = self.base_cell().to_string();
+        for r in 1..=res {
+            let digit = (self.0 >> ((MAX_GRID_RESOLUTION - r) * 3)) & 7;
+            path.push_str(&format!("/{}", digit));
+        }
+        path
+    }
+
+    /// Parses an index from a path produced by `to_path_string`. Returns error
+    /// if the path is malformed or doesn't describe a valid index.
+    pub fn from_path_string(path: &str) -> Result<H3Index> {
+        let invalid = || Error::InvalidPathString(path.to_string());
+        let mut components = path.split('/');
+        let base_cell: u64 = components
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+        let digits = components
+            .map(|d| d.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<u64>>>()?;
+        if base_cell >= 128
+            || digits.len() > MAX_GRID_RESOLUTION as usize
+            || digits.iter().any(|d| *d >= 7)
+        {
+            return Err(invalid());
+        }
+        // Mode 1 (cell), with unused digits set to 7.
+        let mut index =
+            (1 << 59) | ((digits.len() as u64) << 52) | (base_cell << 45) | ((1 << 45) - 1);
+        for (i, digit) in digits.iter().enumerate() {
+            let offset = (MAX_GRID_RESOLUTION as usize - i - 1) * 3;
+            index = (index & !(7 << offset)) | (digit << offset);
+        }
+        H3Index::new(index).map_err(|_| invalid())
+    }
+
+    /// Returns a stable RGB color for the index, for debug map rendering. The
+    /// index bits are mixed (with the SplitMix64 finalizer) so that nearby
+    /// and related indexes get unrelated colors.
+    pub fn debug_color(&self) -> [u8; 3] {
+        let mut z = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        [(z >> 16) as u8, (z >> 8) as u8, z as u8]
+    }
+
     /// Returns the maximum number of icosahedron faces the given H3 index may
     /// intersect.
     fn max_face_count(&self) -> usize {
@@ -62,14 +148,27 @@ impl H3Index {
     /// Return vector of all icosahedron faces intersected by a given H3
     pub fn icosahedron_faces(&self) -> Vec<i32> {
         let num_faces = self.max_face_count();
-        let mut buf = Vec::<i32>::with_capacity(num_faces);
-        let ptr = buf.as_mut_ptr();
+        // Slots for faces the index doesn't intersect are -1.
+        fill_buffer_with(num_faces, -1, |buf| unsafe {
+            h3_sys::h3GetFaces(self.0, buf);
+        })
+    }
+}
+
+/// Decomposes a batch of indexes into parallel arrays of resolution, base cell
+/// and pentagon flags, suitable for building columnar (e.g., Arrow) arrays.
+pub fn decompose_batch(cells: &[H3Index]) -> (Vec<u8>, Vec<i32>, Vec<bool>) {
+    let mut resolutions = Vec::with_capacity(cells.len());
+    let mut base_cells = Vec::with_capacity(cells.len());
+    let mut pentagons = Vec::with_capacity(cells.len());
+    for cell in cells {
         unsafe {
-            std::mem::forget(buf);
-            h3_sys::h3GetFaces(self.0, ptr as *mut i32);
-            Vec::from_raw_parts(ptr, num_faces, num_faces)
+            resolutions.push(h3_sys::h3GetResolution(cell.0) as u8);
+            base_cells.push(h3_sys::h3GetBaseCell(cell.0));
+            pentagons.push(h3_sys::h3IsPentagon(cell.0) != 0);
         }
     }
+    (resolutions, base_cells, pentagons)
 }
 
 impl std::fmt::Display for H3Index {
@@ -78,32 +177,38 @@ impl std::fmt::Display for H3Index {
     }
 }
 
+impl std::fmt::LowerHex for H3Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl std::fmt::UpperHex for H3Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
 impl From<H3Index> for Result<String> {
     fn from(h: H3Index) -> Result<String> {
         // h3 magic number for string buffer width
         const BUF_SIZE: usize = 17;
-        let buf = Vec::<u8>::with_capacity(BUF_SIZE);
-        let ptr = CString::new(buf).expect("CString::new failed!").into_raw();
+        let mut buf = [0 as std::os::raw::c_char; BUF_SIZE];
         unsafe {
-            h3_sys::h3ToString(h.0, ptr, BUF_SIZE);
-            match CString::from_raw(ptr).into_string() {
-                Ok(s) => Ok(s),
+            h3_sys::h3ToString(h.0, buf.as_mut_ptr(), BUF_SIZE);
+            match CStr::from_ptr(buf.as_ptr()).to_str() {
+                Ok(s) => Ok(s.to_string()),
                 Err(_) => Err(Error::UnableToSerialize(h)),
             }
         }
     }
 }
 
-impl From<String> for H3Index {
-    fn from(s: String) -> H3Index {
-        let terminated = CString::new(s).unwrap();
-        unsafe { H3Index(h3_sys::stringToH3(terminated.as_ptr())) }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index::res0_indexes;
+    use std::collections::HashSet;
 
     use geo_types::Point;
 
@@ -127,8 +232,7 @@ mod tests {
 
     #[test]
     fn test_h3_resolution() {
-        for i in 0..MAX_GRID_RESOLUTION + 1 {
-            let res = GridResolution::from_i32(i).expect("GridResolution failed!");
+        for res in GridResolution::iter() {
             let index = Point::new(-122.0553238, 37.3615593)
                 .to_h3_index(res)
                 .unwrap();
@@ -136,4 +240,105 @@ mod tests {
             assert_eq!(index.resolution().expect("Point.to_h3_index failed"), res);
         }
     }
+
+    #[test]
+    fn test_hex_formatting() {
+        let index = H3Index(0x8928308280fffff);
+        assert_eq!(format!("{:x}", index), "8928308280fffff");
+        assert_eq!(format!("{:X}", index), "8928308280FFFFF");
+        assert_eq!(format!("{:#x}", index), "0x8928308280fffff");
+        let s: Result<String> = index.clone().into();
+        assert_eq!(s.unwrap(), format!("{:x}", index));
+    }
+
+    #[test]
+    fn test_to_debug_string() {
+        assert_eq!(
+            H3Index(0x85283473fffffff).to_debug_string(),
+            "res5:bc20:85283473fffffff"
+        );
+    }
+
+    #[test]
+    fn test_decompose_batch() {
+        let cells = vec![
+            H3Index(0x85283473fffffff),
+            H3Index(0x8928308280fffff),
+            H3Index(0x821c07fffffffff),
+        ];
+        let (resolutions, base_cells, pentagons) = decompose_batch(&cells);
+        assert_eq!(resolutions.len(), cells.len());
+        for (i, cell) in cells.iter().enumerate() {
+            assert_eq!(resolutions[i] as i32, cell.resolution().unwrap() as i32);
+            assert_eq!(base_cells[i], cell.base_cell());
+            assert_eq!(pentagons[i], cell.is_pentagon());
+        }
+        assert_eq!(pentagons, vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_is_base_pentagon() {
+        let pentagons: Vec<H3Index> = res0_indexes()
+            .into_iter()
+            .filter(|i| i.is_base_pentagon())
+            .collect();
+        assert_eq!(pentagons.len(), 12);
+        assert!(pentagons.iter().all(|i| i.is_pentagon()));
+        assert!(H3Index(0x821c07fffffffff).is_base_pentagon());
+        assert!(!H3Index(0x8928308280fffff).is_base_pentagon());
+    }
+
+    #[test]
+    fn test_pentagon_axis() {
+        let pentagon = H3Index(0x8009fffffffffff);
+        assert!(pentagon.is_pentagon());
+        assert_eq!(pentagon.pentagon_axis(), Some(4));
+        let neighbor = pentagon
+            .k_ring_indices(1)
+            .into_iter()
+            .find(|cell| !cell.is_pentagon())
+            .unwrap();
+        let cell = neighbor.refine_center(5).unwrap();
+        assert_ne!(cell.base_cell(), 4);
+        assert_eq!(cell.pentagon_axis(), Some(4));
+        assert_eq!(H3Index(0x8928308280fffff).pentagon_axis(), Some(14));
+        assert_eq!(H3Index(0x8003fffffffffff).pentagon_axis(), None);
+    }
+
+    #[test]
+    fn test_path_string() {
+        let index = H3Index(0x8928308280fffff);
+        let path = index.to_path_string();
+        assert_eq!(path, "20/0/6/0/4/0/5/0/0/3");
+        assert_eq!(H3Index::from_path_string(&path), Ok(index.clone()));
+        let parent = index.parent(GridResolution::Z5);
+        assert!(path.starts_with(&parent.to_path_string()));
+        assert_eq!(H3Index(0x8001fffffffffff).to_path_string(), "0");
+        assert_eq!(
+            H3Index::from_path_string("0"),
+            Ok(H3Index(0x8001fffffffffff))
+        );
+        for path in &["20/7", "200", "20/x", "x/0", "", "20//0"] {
+            assert_eq!(
+                H3Index::from_path_string(path),
+                Err(Error::InvalidPathString(path.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_debug_color() {
+        let index = H3Index(0x8928308280fffff);
+        assert_eq!(
+            index.debug_color(),
+            H3Index(0x8928308280fffff).debug_color()
+        );
+        let colors: HashSet<[u8; 3]> = index
+            .k_ring_indices(2)
+            .iter()
+            .chain(&[index.parent(GridResolution::Z8)])
+            .map(|i| i.debug_color())
+            .collect();
+        assert_eq!(colors.len(), 20);
+    }
 }
@@ -30,24 +30,40 @@ extern crate c_vec;
 extern crate geo_types;
 extern crate num_traits;
 
+pub use crate::cellset::*;
+pub use crate::coordij::*;
+pub use crate::edge::*;
 pub use crate::errors::*;
 pub use crate::hierarchy::*;
 pub use crate::index::*;
 pub use crate::inspection::*;
+pub use crate::measure::*;
+pub use crate::polyfill::*;
 pub use crate::region::*;
 pub use crate::resolution::*;
+pub use crate::tile::*;
 pub use crate::traversal::*;
 pub use crate::types::*;
+pub use crate::version::*;
 
+#[cfg(test)]
+mod alloc_counter;
+pub mod cellset;
+pub mod coordij;
+pub mod edge;
 pub mod errors;
 pub mod hierarchy;
 pub mod index;
 pub mod inspection;
+pub mod measure;
+pub mod polyfill;
 mod raw;
 pub mod region;
 pub mod resolution;
+pub mod tile;
 pub mod traversal;
 pub mod types;
+pub mod version;
 
 pub use geo_types::{LineString, MultiPolygon, Point, Polygon};
 
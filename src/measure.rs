@@ -0,0 +1,64 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Distance measurement
+//!
+//! Great-circle (haversine) distances between points, using h3's mean Earth
+//! radius.
+
+use crate::raw::*;
+
+use geo_types::Point;
+
+/// Great-circle distance in radians between two lat/lon points (degrees).
+pub fn point_distance_rads(a: &Point<f64>, b: &Point<f64>) -> f64 {
+    let (a, b) = (GeoCoord::from(*a), GeoCoord::from(*b));
+    unsafe { h3_sys::pointDistRads(&a.0, &b.0) }
+}
+
+/// Great-circle distance in kilometers between two lat/lon points (degrees).
+pub fn point_distance_km(a: &Point<f64>, b: &Point<f64>) -> f64 {
+    let (a, b) = (GeoCoord::from(*a), GeoCoord::from(*b));
+    unsafe { h3_sys::pointDistKm(&a.0, &b.0) }
+}
+
+/// Great-circle distance in meters between two lat/lon points (degrees).
+pub fn point_distance_m(a: &Point<f64>, b: &Point<f64>) -> f64 {
+    let (a, b) = (GeoCoord::from(*a), GeoCoord::from(*b));
+    unsafe { h3_sys::pointDistM(&a.0, &b.0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_distance() {
+        let sf = Point::new(-122.4194, 37.7749);
+        let nyc = Point::new(-74.0060, 40.7128);
+        assert!((point_distance_km(&sf, &nyc) - 4129.).abs() < 1.);
+        assert_relative_eq!(
+            point_distance_m(&sf, &nyc),
+            point_distance_km(&sf, &nyc) * 1.0e3,
+            max_relative = 1e-9
+        );
+        assert_relative_eq!(
+            point_distance_rads(&sf, &nyc),
+            point_distance_km(&sf, &nyc) / 6371.007180918475,
+            max_relative = 1e-9
+        );
+        assert_eq!(point_distance_m(&sf, &sf), 0.);
+    }
+}
@@ -0,0 +1,293 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configurable polyfill
+//!
+//! `PolyfillOptions` collects the optional behaviors for covering a polygon
+//! with H3 indexes behind a single builder.
+
+use crate::errors::*;
+use crate::raw::*;
+use crate::region::*;
+use crate::resolution::*;
+use crate::types::*;
+
+use geo_types::{Coordinate, LineString, Polygon};
+use std::collections::HashSet;
+
+/// Which cells of the covering are returned.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CoverageMode {
+    /// Cells whose centroid lies within the polygon, as in `polyfill`.
+    #[default]
+    Center,
+    /// Cells of the centroid covering with no neighbor outside of it, i.e.
+    /// those `polyfill_classified` doesn't mark as boundary cells.
+    Interior,
+}
+
+/// Builder for polyfill options. The defaults match `polyfill`.
+#[derive(Clone, Debug, Default)]
+pub struct PolyfillOptions {
+    coverage_mode: CoverageMode,
+    simplify_tolerance: Option<f64>,
+    parallel: bool,
+    antimeridian_split: bool,
+}
+
+impl PolyfillOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets which cells of the covering are returned.
+    pub fn coverage_mode(mut self, mode: CoverageMode) -> Self {
+        self.coverage_mode = mode;
+        self
+    }
+
+    /// Simplifies the polygon's rings (Douglas-Peucker, with the tolerance in
+    /// degrees) before filling.
+    pub fn simplify_tolerance(mut self, tolerance: f64) -> Self {
+        self.simplify_tolerance = Some(tolerance);
+        self
+    }
+
+    /// Requests that the fill be spread across threads. Filling is currently
+    /// single-threaded, so this has no effect on the result or how it's
+    /// computed.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Splits polygons with edges spanning more than 180 degrees of longitude
+    /// at the antimeridian, filling each side separately.
+    pub fn antimeridian_split(mut self, split: bool) -> Self {
+        self.antimeridian_split = split;
+        self
+    }
+
+    /// Returns the sorted, de-duplicated H3Index's covering the polygon, first
+    /// checking that the polygon is well-formed.
+    pub fn fill(&self, polygon: &Polygon<f64>, res: GridResolution) -> Result<Vec<H3Index>> {
+        let mut polygon = match self.simplify_tolerance {
+            Some(tolerance) => simplify_polygon(polygon, tolerance),
+            None => polygon.clone(),
+        };
+        validate_polygon(&polygon)?;
+        for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+            if ring.num_coords() > 0 {
+                validate_ring(ring)?;
+            }
+        }
+        let split = self.antimeridian_split && crosses_antimeridian(polygon.exterior());
+        if split {
+            polygon = map_coords(&polygon, |c| Coordinate {
+                x: if c.x < 0. { c.x + 360. } else { c.x },
+                y: c.y,
+            });
+        }
+        let parts = if split {
+            let shift = |c: Coordinate<f64>| Coordinate {
+                x: c.x - 360.,
+                y: c.y,
+            };
+            vec![
+                clip_polygon(&polygon, 180., true),
+                map_coords(&clip_polygon(&polygon, 180., false), shift),
+            ]
+        } else {
+            vec![polygon.clone()]
+        };
+        let cells: HashSet<H3Index> = parts.iter().flat_map(|p| polyfill_cells(p, res)).collect();
+        let mut result: Vec<H3Index> = match self.coverage_mode {
+            CoverageMode::Center => cells.into_iter().collect(),
+            CoverageMode::Interior => cells
+                .iter()
+                .filter(|cell| !on_covering_boundary(cell, &cells))
+                .cloned()
+                .collect(),
+        };
+        result.sort();
+        Ok(result)
+    }
+}
+
+/// Does any edge of the ring span more than 180 degrees of longitude?
+fn crosses_antimeridian(ring: &LineString<f64>) -> bool {
+    ring.lines().any(|l| l.dx().abs() > 180.)
+}
+
+fn map_coords<F: Fn(Coordinate<f64>) -> Coordinate<f64>>(p: &Polygon<f64>, f: F) -> Polygon<f64> {
+    let ring = |r: &LineString<f64>| -> LineString<f64> { r.0.iter().map(|c| f(*c)).collect() };
+    Polygon::new(ring(p.exterior()), p.interiors().iter().map(ring).collect())
+}
+
+/// Clips the ring to the half-plane left (or right) of the meridian x
+/// (Sutherland-Hodgman).
+fn clip_ring(ring: &LineString<f64>, x: f64, keep_left: bool) -> LineString<f64> {
+    let inside = |c: Coordinate<f64>| if keep_left { c.x <= x } else { c.x >= x };
+    let mut coords = Vec::new();
+    for line in ring.lines() {
+        let (a, b) = (line.start, line.end);
+        if inside(a) {
+            coords.push(a);
+        }
+        if inside(a) != inside(b) {
+            let t = (x - a.x) / (b.x - a.x);
+            coords.push(Coordinate {
+                x,
+                y: a.y + t * (b.y - a.y),
+            });
+        }
+    }
+    coords.into()
+}
+
+/// Clips the polygon to the half-plane left (or right) of the meridian x,
+/// dropping holes that become degenerate.
+fn clip_polygon(p: &Polygon<f64>, x: f64, keep_left: bool) -> Polygon<f64> {
+    Polygon::new(
+        clip_ring(p.exterior(), x, keep_left),
+        p.interiors()
+            .iter()
+            .map(|r| clip_ring(r, x, keep_left))
+            .filter(|r| validate_ring(r).is_ok())
+            .collect(),
+    )
+}
+
+/// Distance from c to the segment (a, b), in degrees.
+fn segment_distance(c: Coordinate<f64>, a: Coordinate<f64>, b: Coordinate<f64>) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length2 = dx * dx + dy * dy;
+    let t = if length2 > 0. {
+        (((c.x - a.x) * dx + (c.y - a.y) * dy) / length2).clamp(0., 1.)
+    } else {
+        0.
+    };
+    ((c.x - a.x - t * dx).powi(2) + (c.y - a.y - t * dy).powi(2)).sqrt()
+}
+
+/// Appends the Douglas-Peucker simplification of coords to out, excluding the
+/// last coordinate.
+fn douglas_peucker(coords: &[Coordinate<f64>], tolerance: f64, out: &mut Vec<Coordinate<f64>>) {
+    let (first, last) = (coords[0], coords[coords.len() - 1]);
+    let (index, distance) = (1..coords.len().saturating_sub(1))
+        .map(|i| (i, segment_distance(coords[i], first, last)))
+        .fold((0, 0.), |max, d| if d.1 > max.1 { d } else { max });
+    if distance > tolerance {
+        douglas_peucker(&coords[..=index], tolerance, out);
+        douglas_peucker(&coords[index..], tolerance, out);
+    } else {
+        out.push(first);
+    }
+}
+
+/// Simplifies the ring, keeping it unchanged if it would become degenerate.
+pub(crate) fn simplify_ring(ring: &LineString<f64>, tolerance: f64) -> LineString<f64> {
+    if ring.num_coords() < 2 {
+        return ring.clone();
+    }
+    let mut coords = Vec::new();
+    douglas_peucker(&ring.0, tolerance, &mut coords);
+    coords.push(ring.0[ring.0.len() - 1]);
+    let simplified: LineString<f64> = coords.into();
+    if validate_ring(&simplified).is_ok() {
+        simplified
+    } else {
+        ring.clone()
+    }
+}
+
+fn simplify_polygon(p: &Polygon<f64>, tolerance: f64) -> Polygon<f64> {
+    Polygon::new(
+        simplify_ring(p.exterior(), tolerance),
+        p.interiors()
+            .iter()
+            .map(|r| simplify_ring(r, tolerance))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::tests::sf_polygon as sf;
+    use geo_types::polygon;
+
+    #[test]
+    fn test_default_options() {
+        let res = GridResolution::Z9;
+        let mut expected: Vec<H3Index> = sf()
+            .polyfill(res)
+            .into_iter()
+            .filter(|i| *i != H3Index(0))
+            .collect();
+        expected.sort();
+        assert_eq!(
+            PolyfillOptions::new().fill(&sf(), res),
+            Ok(expected.clone())
+        );
+        let parallel = PolyfillOptions::new().parallel(true).fill(&sf(), res);
+        assert_eq!(parallel, Ok(expected.clone()));
+        let simplified = PolyfillOptions::new()
+            .simplify_tolerance(1e-9)
+            .fill(&sf(), res);
+        assert_eq!(simplified, Ok(expected));
+    }
+
+    #[test]
+    fn test_coverage_modes() {
+        let res = GridResolution::Z8;
+        let interior = PolyfillOptions::new()
+            .coverage_mode(CoverageMode::Interior)
+            .parallel(true)
+            .fill(&sf(), res)
+            .unwrap();
+        let expected: Vec<H3Index> = polyfill_classified(&sf(), res)
+            .into_iter()
+            .filter(|(_, boundary)| !boundary)
+            .map(|(cell, _)| cell)
+            .collect();
+        assert!(!interior.is_empty());
+        assert_eq!(interior, expected);
+        let center = PolyfillOptions::new()
+            .coverage_mode(CoverageMode::Center)
+            .fill(&sf(), res)
+            .unwrap();
+        assert!(interior.len() < center.len());
+    }
+
+    #[test]
+    fn test_antimeridian_split() {
+        let poly = polygon![
+            (x: 179.5, y: 0.5),
+            (x: -179.5, y: 0.5),
+            (x: -179.5, y: -0.5),
+            (x: 179.5, y: -0.5),
+        ];
+        let cells = PolyfillOptions::new()
+            .antimeridian_split(true)
+            .simplify_tolerance(0.01)
+            .fill(&poly, GridResolution::Z5)
+            .unwrap();
+        let lngs: Vec<f64> = cells.iter().map(|c| c.centroid().lng()).collect();
+        assert!(lngs.iter().all(|lng| lng.abs() > 179.));
+        assert!(lngs.iter().any(|lng| *lng > 0.));
+        assert!(lngs.iter().any(|lng| *lng < 0.));
+    }
+}
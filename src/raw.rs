@@ -15,13 +15,28 @@
 
 //! Aliased sys/raw types
 
+use crate::errors::*;
+use crate::types::*;
+
 use c_vec::CVec;
 use geo_types::{Coordinate, LineString, MultiPolygon, Point, Polygon};
 
 pub struct GeoCoord(pub h3_sys::GeoCoord);
 pub struct GeoBoundary(pub h3_sys::GeoBoundary);
-pub struct GeoFence(pub h3_sys::Geofence);
-pub struct GeoPolygon(pub h3_sys::GeoPolygon);
+/// A ring for h3, along with the vertex storage it points into when converted
+/// from Rust (empty when viewing a ring owned by h3). Moving the Vec doesn't
+/// move its buffer, so the pointer stays valid until the GeoFence is dropped.
+pub struct GeoFence(
+    pub h3_sys::Geofence,
+    #[allow(dead_code)] Vec<h3_sys::GeoCoord>,
+);
+/// A polygon for h3, along with the rings and holes array it points into when
+/// converted from Rust (empty when viewing a polygon owned by h3).
+pub struct GeoPolygon(
+    pub h3_sys::GeoPolygon,
+    #[allow(dead_code)] Vec<GeoFence>,
+    #[allow(dead_code)] Vec<h3_sys::Geofence>,
+);
 pub struct GeoMultiPolygon(pub h3_sys::GeoMultiPolygon);
 
 impl From<Point<f64>> for GeoCoord {
@@ -65,38 +80,103 @@ impl From<GeoFence> for LineString<f64> {
     }
 }
 
-impl From<LineString<f64>> for GeoFence {
-    #[allow(unused_unsafe)]
-    fn from(c: LineString<f64>) -> GeoFence {
-        let num_verts = c.num_coords() as i32;
-        // YUCK
-        let mut v: Vec<h3_sys::GeoCoord> = c
-            .into_points()
+/// Tolerance in degrees within which a ring's last vertex is considered a
+/// duplicate of its first.
+const CLOSING_TOLERANCE: f64 = 1e-9;
+
+impl From<&LineString<f64>> for GeoFence {
+    fn from(c: &LineString<f64>) -> GeoFence {
+        // h3 treats rings as implicitly closed, so drop a (nearly) duplicate
+        // closing vertex rather than counting it as another vertex.
+        let mut coords = &c.0[..];
+        if let (Some(first), Some(last)) = (coords.first(), coords.last()) {
+            if coords.len() > 1
+                && (first.x - last.x).abs() <= CLOSING_TOLERANCE
+                && (first.y - last.y).abs() <= CLOSING_TOLERANCE
+            {
+                coords = &coords[..coords.len() - 1];
+            }
+        }
+        let mut v: Vec<h3_sys::GeoCoord> = coords
             .iter()
-            .map(|&c| -> h3_sys::GeoCoord {
-                let f: GeoCoord = c.into();
-                f.0
-            })
+            .map(|&c| GeoCoord::from(Point::from(c)).0)
             .collect();
-        let ptr = v.as_mut_ptr();
-        unsafe {
-            std::mem::forget(v);
-            GeoFence(h3_sys::Geofence {
-                numVerts: num_verts,
-                verts: ptr,
-            })
-        }
+        let fence = h3_sys::Geofence {
+            numVerts: v.len() as i32,
+            verts: v.as_mut_ptr(),
+        };
+        GeoFence(fence, v)
+    }
+}
+
+impl From<LineString<f64>> for GeoFence {
+    fn from(c: LineString<f64>) -> GeoFence {
+        GeoFence::from(&c)
+    }
+}
+
+/// Allocates a buffer of default (i.e., zero) values, passes it to the closure
+/// to be filled by h3, and returns it. Slots that h3 doesn't fill keep their
+/// default value rather than being left uninitialized.
+///
+/// Miri can't execute the h3 FFI calls, so rather than checking callers under
+/// Miri, the tests check that slots h3 leaves unfilled read back as the
+/// initial value.
+pub fn fill_buffer<T: Default + Clone>(size: usize, f: impl FnOnce(*mut T)) -> Vec<T> {
+    fill_buffer_with(size, T::default(), f)
+}
+
+/// Like `fill_buffer`, but initializes the buffer with the given sentinel,
+/// for outputs where the default value is meaningful.
+pub fn fill_buffer_with<T: Clone>(size: usize, sentinel: T, f: impl FnOnce(*mut T)) -> Vec<T> {
+    let mut buf = vec![sentinel; size];
+    f(buf.as_mut_ptr());
+    buf
+}
+
+/// Number of distinct vertices in the ring, or None if the ring contains
+/// non-finite coordinates.
+fn num_distinct_coords(c: &LineString<f64>) -> Option<usize> {
+    if c.0.iter().any(|p| !p.x.is_finite() || !p.y.is_finite()) {
+        return None;
+    }
+    let mut coords: Vec<(f64, f64)> = c.0.iter().map(|p| (p.x, p.y)).collect();
+    coords.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    coords.dedup();
+    Some(coords.len())
+}
+
+/// Checks that the ring can be safely handed off to h3.
+pub fn validate_ring(c: &LineString<f64>) -> Result<()> {
+    match num_distinct_coords(c) {
+        Some(n) if n >= 3 => Ok(()),
+        _ => Err(Error::InvalidRing(c.clone())),
+    }
+}
+
+// NB: The fallible conversions below are inherent constructors, since a
+// TryFrom impl would conflict with the blanket impl over the From impls.
+
+impl GeoFence {
+    /// Converts the ring, rejecting rings that h3 can't handle.
+    pub fn try_new(c: LineString<f64>) -> Result<GeoFence> {
+        validate_ring(&c)?;
+        Ok(c.into())
     }
 }
 
 impl From<GeoBoundary> for LineString<f64> {
     fn from(c: GeoBoundary) -> LineString<f64> {
-        let num_vertices = c.0.numVerts as usize;
+        // h3 doesn't validate indexes, so the boundary of an invalid one may
+        // have a garbage vertex count or coordinates.
+        let num_vertices =
+            (c.0.numVerts.max(0) as usize).min(h3_sys::MAX_CELL_BNDRY_VERTS as usize);
         let verts: Vec<Coordinate<f64>> =
             c.0.verts
                 .iter()
                 .take(num_vertices)
                 .map(|c| GeoCoord(*c).into())
+                .filter(|c: &Coordinate<f64>| c.x.is_finite() && c.y.is_finite())
                 .collect();
         verts.into()
     }
@@ -107,31 +187,83 @@ impl From<GeoPolygon> for Polygon<f64> {
         let num_holes = p.0.numHoles as usize;
         let holes: Vec<h3_sys::Geofence> = unsafe { CVec::new(p.0.holes, num_holes).into() };
         Polygon::new(
-            GeoFence(p.0.geofence).into(),
+            GeoFence(p.0.geofence, vec![]).into(),
             holes
                 .iter()
-                .map(|&h| -> LineString<f64> { GeoFence(h).into() })
+                .map(|&h| -> LineString<f64> { GeoFence(h, vec![]).into() })
                 .collect(),
         )
     }
 }
+/// Canonical vertex sequence of a ring, independent of closure, starting
+/// vertex and winding order, for comparing rings geometrically.
+fn canonical_ring(c: &LineString<f64>) -> Vec<(f64, f64)> {
+    let mut coords: Vec<(f64, f64)> = c.0.iter().map(|p| (p.x, p.y)).collect();
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords.pop();
+    }
+    // A total order, so that rings with non-finite coordinates still compare.
+    let cmp = |a: &(f64, f64), b: &(f64, f64)| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1));
+    let rotated = |mut v: Vec<(f64, f64)>| {
+        if let Some(start) = (0..v.len()).min_by(|&i, &j| cmp(&v[i], &v[j])) {
+            v.rotate_left(start);
+        }
+        v
+    };
+    let reversed: Vec<(f64, f64)> = coords.iter().rev().cloned().collect();
+    let (forward, backward) = (rotated(coords), rotated(reversed));
+    let order = forward
+        .iter()
+        .zip(&backward)
+        .map(|(a, b)| cmp(a, b))
+        .find(|o| o.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal);
+    if order.is_gt() {
+        backward
+    } else {
+        forward
+    }
+}
+
+impl From<&Polygon<f64>> for GeoPolygon {
+    fn from(p: &Polygon<f64>) -> GeoPolygon {
+        // Drop empty interior LineStrings (creating polygon! with
+        // interiors=[[]] would otherwise return num_holes=1), as well as holes
+        // duplicating another hole or the exterior, all of which crash
+        // polyfill.
+        let mut seen = vec![canonical_ring(p.exterior())];
+        let mut fences = vec![GeoFence::from(p.exterior())];
+        for interior in p.interiors() {
+            let key = canonical_ring(interior);
+            if interior.num_coords() > 0 && !seen.contains(&key) {
+                seen.push(key);
+                fences.push(GeoFence::from(interior));
+            }
+        }
+        let mut holes: Vec<h3_sys::Geofence> = fences[1..].iter().map(|f| f.0).collect();
+        let polygon = h3_sys::GeoPolygon {
+            geofence: fences[0].0,
+            numHoles: holes.len() as i32,
+            holes: holes.as_mut_ptr(),
+        };
+        GeoPolygon(polygon, fences, holes)
+    }
+}
+
 impl From<Polygon<f64>> for GeoPolygon {
     fn from(p: Polygon<f64>) -> GeoPolygon {
-        let (exterior, interiors) = p.into_inner();
-        let geofence: GeoFence = exterior.into();
-        // Explicitly count the number of interior LineStrings that are
-        // non-empty. Creating polygon! with interiors=[[]] would otherwise
-        // return num_holes=1, causing a downstream segfault.
-        let num_holes: i32 = interiors.iter().map(|j| (j.num_coords() > 0) as i32).sum();
-        let mut holes: Vec<GeoFence> = interiors
-            .into_iter()
-            .map(|g| -> GeoFence { g.into() })
-            .collect();
-        GeoPolygon(h3_sys::GeoPolygon {
-            geofence: geofence.0,
-            numHoles: num_holes,
-            holes: holes.as_mut_ptr() as *mut h3_sys::Geofence,
-        })
+        GeoPolygon::from(&p)
+    }
+}
+
+impl GeoPolygon {
+    /// Converts the polygon, rejecting degenerate exterior or interior rings.
+    pub fn try_new(p: &Polygon<f64>) -> Result<GeoPolygon> {
+        validate_ring(p.exterior())?;
+        for interior in p.interiors().iter().filter(|j| j.num_coords() > 0) {
+            validate_ring(interior)?;
+        }
+        Ok(p.into())
     }
 }
 
@@ -141,7 +273,7 @@ impl From<GeoMultiPolygon> for MultiPolygon<f64> {
         let poly: Vec<h3_sys::GeoPolygon> = unsafe { CVec::new(p.0.polygons, num_poly).into() };
         MultiPolygon(
             poly.iter()
-                .map(|p| -> Polygon<f64> { GeoPolygon(*p).into() })
+                .map(|p| -> Polygon<f64> { GeoPolygon(*p, vec![], vec![]).into() })
                 .collect(),
         )
     }
@@ -150,7 +282,8 @@ impl From<GeoMultiPolygon> for MultiPolygon<f64> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use geo_types::polygon;
+    use crate::resolution::GridResolution;
+    use geo_types::{line_string, polygon};
 
     /// Sanity check around round tripping points between h3-rs FFI and Rust
     /// geotypes.
@@ -176,7 +309,7 @@ mod tests {
         ];
         let polygon: GeoPolygon = poly.into();
         assert_eq!(polygon.0.numHoles, 0);
-        assert_eq!(polygon.0.geofence.numVerts, 4);
+        assert_eq!(polygon.0.geofence.numVerts, 3);
         let poly = polygon![
             exterior: [
                 (x: -122.4089866999972145, y: 37.813318999983238),
@@ -187,7 +320,146 @@ mod tests {
         ];
         let polygon: GeoPolygon = poly.into();
         assert_eq!(polygon.0.numHoles, 0);
-        assert_eq!(polygon.0.geofence.numVerts, 4);
+        assert_eq!(polygon.0.geofence.numVerts, 3);
+    }
+
+    #[test]
+    fn test_borrowed_polygon_conversion() {
+        let poly = polygon![
+            exterior: [
+                (x: -122.40, y: 37.81),
+                (x: -122.38, y: 37.78),
+                (x: -122.35, y: 37.71),
+                (x: -122.40, y: 37.81)
+            ],
+            interiors: [[
+                (x: -122.38, y: 37.77),
+                (x: -122.37, y: 37.76),
+                (x: -122.38, y: 37.75)
+            ]]
+        ];
+        let borrowed = GeoPolygon::from(&poly);
+        assert_eq!(
+            Polygon::from(borrowed),
+            Polygon::from(GeoPolygon::from(poly.clone()))
+        );
+        let borrowed = GeoPolygon::from(&poly);
+        assert_eq!(borrowed.0.numHoles, 1);
+        assert_eq!(borrowed.0.geofence.numVerts, 3);
+        // The source polygon is untouched, closing vertex included.
+        assert_eq!(poly.exterior().num_coords(), 4);
+    }
+
+    #[test]
+    fn test_boundary_from_invalid_index() {
+        let mut gb = h3_sys::GeoBoundary::default();
+        unsafe {
+            h3_sys::h3ToGeoBoundary(0, &mut gb);
+        }
+        let boundary: LineString<f64> = GeoBoundary(gb).into();
+        assert!(boundary.num_coords() <= h3_sys::MAX_CELL_BNDRY_VERTS as usize);
+
+        // A garbage vertex count is clamped, and non-finite vertices dropped.
+        gb.numVerts = 1000;
+        gb.verts[1].lat = f64::NAN;
+        gb.verts[2].lon = f64::INFINITY;
+        let boundary: LineString<f64> = GeoBoundary(gb).into();
+        assert_eq!(
+            boundary.num_coords(),
+            h3_sys::MAX_CELL_BNDRY_VERTS as usize - 2
+        );
+        assert!(boundary
+            .points_iter()
+            .all(|p| p.lat().is_finite() && p.lng().is_finite()));
+        gb.numVerts = -1;
+        assert_eq!(LineString::from(GeoBoundary(gb)).num_coords(), 0);
+    }
+
+    #[test]
+    fn test_fill_buffer() {
+        let buf = fill_buffer(3, |buf: *mut H3Index| unsafe {
+            *buf.add(1) = H3Index(0x8928308280fffff);
+        });
+        assert_eq!(
+            buf,
+            vec![H3Index(0), H3Index(0x8928308280fffff), H3Index(0)]
+        );
+        let origin = H3Index(0x8928308280fffff);
+        let ring = fill_buffer(7, |buf: *mut H3Index| unsafe {
+            h3_sys::kRing(origin.0, 1, buf as *mut h3_sys::H3Index);
+        });
+        assert_eq!(ring, origin.k_ring_indices(1));
+        assert!(fill_buffer::<i32>(0, |_| ()).is_empty());
+
+        // Unfilled slots read back as the initial value: the missing child of
+        // a pentagon, and faces that an index doesn't intersect.
+        let pentagon = H3Index(0x821c07fffffffff);
+        let children = pentagon.children(GridResolution::Z3);
+        assert_eq!(children.len(), 7);
+        assert_eq!(children.iter().filter(|c| **c == H3Index(0)).count(), 1);
+        assert!(children
+            .iter()
+            .filter(|c| **c != H3Index(0))
+            .all(|c| c.is_valid()));
+        let faces = origin.icosahedron_faces();
+        assert_eq!(faces.iter().filter(|f| **f >= 0).count(), 1);
+        assert!(faces.iter().all(|f| *f == -1 || (0..20).contains(f)));
+        let faces = pentagon.icosahedron_faces();
+        assert_eq!(faces.iter().filter(|f| **f >= 0).count(), 5);
+    }
+
+    #[test]
+    fn test_almost_closed_ring() {
+        let ring = line_string![
+            (x: -122.40, y: 37.81),
+            (x: -122.38, y: 37.78),
+            (x: -122.35, y: 37.71),
+            (x: -122.40 + 1e-12, y: 37.81 - 1e-12)
+        ];
+        let fence: GeoFence = ring.into();
+        assert_eq!(fence.0.numVerts, 3);
+        let ring = line_string![
+            (x: -122.40, y: 37.81),
+            (x: -122.38, y: 37.78),
+            (x: -122.35, y: 37.71),
+            (x: -122.40, y: 37.81)
+        ];
+        let fence: GeoFence = ring.into();
+        assert_eq!(fence.0.numVerts, 3);
+        let ring = line_string![
+            (x: -122.40, y: 37.81),
+            (x: -122.38, y: 37.78),
+            (x: -122.35, y: 37.71),
+            (x: -122.41, y: 37.81)
+        ];
+        let fence: GeoFence = ring.into();
+        assert_eq!(fence.0.numVerts, 4);
+    }
+
+    #[test]
+    fn test_degenerate_ring() {
+        let ring = line_string![(x: -122.40, y: 37.81), (x: -122.38, y: 37.78)];
+        assert_eq!(
+            GeoFence::try_new(ring.clone()).err(),
+            Some(Error::InvalidRing(ring))
+        );
+        let ring = line_string![
+            (x: -122.40, y: 37.81),
+            (x: -122.38, y: f64::NAN),
+            (x: -122.35, y: 37.71)
+        ];
+        assert!(GeoFence::try_new(ring).is_err());
+        let ring = line_string![
+            (x: -122.40, y: 37.81),
+            (x: -122.38, y: 37.78),
+            (x: -122.35, y: 37.71)
+        ];
+        assert!(GeoFence::try_new(ring).is_ok());
+        let poly = polygon![
+            exterior: [(x: -122.40, y: 37.81), (x: -122.38, y: 37.78)],
+            interiors: []
+        ];
+        assert!(GeoPolygon::try_new(&poly).is_err());
     }
 
     #[test]
@@ -211,6 +483,30 @@ mod tests {
         );
         let polygon: GeoPolygon = poly.into();
         assert_eq!(polygon.0.numHoles, 1);
-        assert_eq!(polygon.0.geofence.numVerts, 7);
+        assert_eq!(polygon.0.geofence.numVerts, 6);
+    }
+
+    #[test]
+    fn test_duplicate_holes() {
+        let hole = line_string![
+            (x: -122.4471197, y: 37.7869802),
+            (x: -122.4590777, y: 37.7664102),
+            (x: -122.4137097, y: 37.7710682)
+        ];
+        // The same ring, starting elsewhere and wound the other way.
+        let rewound = line_string![
+            (x: -122.4590777, y: 37.7664102),
+            (x: -122.4471197, y: 37.7869802),
+            (x: -122.4137097, y: 37.7710682)
+        ];
+        let exterior = line_string![
+            (x: -122.4089866, y: 37.8133189),
+            (x: -122.3805436, y: 37.7866302),
+            (x: -122.3544736, y: 37.7198061),
+            (x: -122.5123436, y: 37.7076131)
+        ];
+        let poly = Polygon::new(exterior.clone(), vec![hole.clone(), rewound, exterior]);
+        let polygon: GeoPolygon = poly.into();
+        assert_eq!(polygon.0.numHoles, 1);
     }
 }
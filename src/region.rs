@@ -15,11 +15,19 @@
 
 //! Conversions H3 indexes to and from polygonal regions
 
-use crate::index::*;
+use crate::errors::*;
+use crate::hierarchy::*;
+use crate::index::{point_to_unit_vector, unit_vector_to_point};
+use crate::polyfill::simplify_ring;
 use crate::raw::*;
 use crate::resolution::*;
+use crate::types::*;
 
-use geo_types::{LineString, MultiPolygon, Polygon};
+use geo_types::{Coordinate, LineString, MultiPolygon, Point, Polygon};
+use geojson::{feature::Id, Feature, FeatureCollection, GeoJson, Geometry, Value};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::io::Read;
 
 // Coercion of H3-internal GeoJSON types to geo-types GeoJSON types.
 
@@ -37,45 +45,683 @@ impl From<H3Index> for LineString<f64> {
     }
 }
 
+impl H3Index {
+    /// Returns the cell's centroid together with its boundary, for rendering.
+    pub fn geometry(&self) -> (Point<f64>, LineString<f64>) {
+        (self.centroid(), self.clone().into())
+    }
+
+    /// Returns the great-circle midpoint of each of the cell's edges (six for
+    /// hexagons, five for pentagons), e.g., for placing edge labels.
+    pub fn edge_midpoints(&self) -> Vec<Point<f64>> {
+        self.unidirectional_edges()
+            .iter()
+            .filter_map(|edge| {
+                let boundary = edge.boundary();
+                let (a, b) = (boundary.0.first()?, boundary.0.last()?);
+                let (u, v) = (
+                    point_to_unit_vector((*a).into()),
+                    point_to_unit_vector((*b).into()),
+                );
+                Some(unit_vector_to_point([
+                    u[0] + v[0],
+                    u[1] + v[1],
+                    u[2] + v[2],
+                ]))
+            })
+            .collect()
+    }
+
+    /// Returns a regular polygon of the given number of vertices (at least 3)
+    /// inscribed at the cell's inradius around its centroid, for cheaply
+    /// rendering cells as uniform markers.
+    pub fn to_circle_approx(&self, segments: usize) -> Polygon<f64> {
+        let dot = |a: [f64; 3], b: [f64; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let center = self.to_cartesian();
+        let vertices: Vec<[f64; 3]> = LineString::from(self.clone())
+            .into_points()
+            .into_iter()
+            .map(point_to_unit_vector)
+            .collect();
+        // The inradius is the angular distance to the nearest edge midpoint.
+        let radius = (0..vertices.len())
+            .map(|i| {
+                let (a, b) = (vertices[i], vertices[(i + 1) % vertices.len()]);
+                let mid = [a[0] + b[0], a[1] + b[1], a[2] + b[2]];
+                let norm = dot(mid, mid).sqrt();
+                dot(center, mid) / norm
+            })
+            .fold(1f64, f64::min)
+            .clamp(-1., 1.)
+            .acos();
+        // Tangent basis (north, east) at the centroid.
+        let east = {
+            let v = [-center[1], center[0], 0.];
+            let norm = dot(v, v).sqrt();
+            if norm < f64::EPSILON {
+                [0., 1., 0.]
+            } else {
+                [v[0] / norm, v[1] / norm, 0.]
+            }
+        };
+        let north = [
+            center[1] * east[2] - center[2] * east[1],
+            center[2] * east[0] - center[0] * east[2],
+            center[0] * east[1] - center[1] * east[0],
+        ];
+        let segments = segments.max(3);
+        let ring: Vec<Coordinate<f64>> = (0..segments)
+            .map(|i| {
+                let bearing = 2. * std::f64::consts::PI * i as f64 / segments as f64;
+                let (s, c) = (radius.sin(), radius.cos());
+                let v = [0, 1, 2].map(|k| {
+                    c * center[k] + s * (bearing.cos() * north[k] + bearing.sin() * east[k])
+                });
+                unit_vector_to_point(v).into()
+            })
+            .collect();
+        Polygon::new(ring.into(), vec![])
+    }
+}
+
 pub trait ToH3Region {
     /// Returns H3Index's covering the given region.
     fn polyfill(&self, res: GridResolution) -> Vec<H3Index>;
 
     /// Maximum number of hexagons in the given region.
     fn polyfill_size(&self, res: GridResolution) -> usize;
+
+    /// Checks that the region is well-formed, i.e., safe to polyfill. By
+    /// default every region is accepted.
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns H3Index's covering the given region, first checking that the
+    /// region is well-formed.
+    fn try_polyfill(&self, res: GridResolution) -> Result<Vec<H3Index>> {
+        self.validate()?;
+        Ok(self.polyfill(res))
+    }
+}
+
+/// Signed area of the triangle (a, b, c), used to determine orientation.
+fn orientation(a: Coordinate<f64>, b: Coordinate<f64>, c: Coordinate<f64>) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// Do the segments (p1, p2) and (q1, q2) properly intersect?
+fn segments_intersect(
+    p1: Coordinate<f64>,
+    p2: Coordinate<f64>,
+    q1: Coordinate<f64>,
+    q2: Coordinate<f64>,
+) -> bool {
+    let d1 = orientation(q1, q2, p1);
+    let d2 = orientation(q1, q2, p2);
+    let d3 = orientation(p1, p2, q1);
+    let d4 = orientation(p1, p2, q2);
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+/// Returns true if any two non-adjacent segments of the ring intersect.
+fn is_self_intersecting(ring: &LineString<f64>) -> bool {
+    let segments: Vec<_> = ring.lines().collect();
+    let n = segments.len();
+    for i in 0..n {
+        for j in (i + 2)..n {
+            // The first and last segments of a closed ring are adjacent.
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (a, b) = (segments[i], segments[j]);
+            if segments_intersect(a.start, a.end, b.start, b.end) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Checks that the polygon can be safely handed off to h3.
+pub(crate) fn validate_polygon(p: &Polygon<f64>) -> Result<()> {
+    if is_self_intersecting(p.exterior()) {
+        Err(Error::InvalidPolygon(p.clone()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns H3Index's covering the already-converted polygon.
+fn polyfill_geo_polygon(polygon: &GeoPolygon, res: GridResolution) -> Vec<H3Index> {
+    let max_indices = unsafe { h3_sys::maxPolyfillSize(&polygon.0, res as i32) as usize };
+    // h3 uses the output buffer as a hash set, and expects it zeroed.
+    fill_buffer(max_indices, |buf: *mut H3Index| unsafe {
+        h3_sys::polyfill(&polygon.0, res as i32, buf as *mut h3_sys::H3Index);
+    })
 }
 
 impl ToH3Region for Polygon<f64> {
     fn polyfill(&self, res: GridResolution) -> Vec<H3Index> {
-        let polygon: GeoPolygon = self.clone().into();
-        let max_indices = self.polyfill_size(res);
-        let mut buf = Vec::<H3Index>::with_capacity(max_indices);
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            h3_sys::polyfill(&polygon.0, res as i32, ptr as *mut h3_sys::H3Index);
-            Vec::from_raw_parts(ptr, max_indices, max_indices)
-        }
+        polyfill_geo_polygon(&self.into(), res)
     }
 
     fn polyfill_size(&self, res: GridResolution) -> usize {
-        let polygon: GeoPolygon = self.clone().into();
+        let polygon: GeoPolygon = self.into();
         println!("{} {}", &polygon.0.geofence.numVerts, &polygon.0.numHoles);
         unsafe { h3_sys::maxPolyfillSize(&polygon.0, res as i32) as usize }
     }
+
+    fn validate(&self) -> Result<()> {
+        validate_polygon(self)?;
+        validate_ring(self.exterior())?;
+        for interior in self.interiors().iter().filter(|j| j.num_coords() > 0) {
+            validate_ring(interior)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns H3Index's covering the polygon, without the empty (zero) entries
+/// that h3 pads polyfill results with.
+pub(crate) fn polyfill_cells(p: &Polygon<f64>, res: GridResolution) -> Vec<H3Index> {
+    let mut cells = p.polyfill(res);
+    cells.retain(|i| *i != H3Index(0));
+    cells
+}
+
+/// Polyfills each polygon in turn. Cells along an edge shared by adjacent
+/// polygons may be produced by both, so the results are sorted and
+/// deduplicated, and unlike for a single polygon contain no empty entries.
+impl ToH3Region for MultiPolygon<f64> {
+    fn polyfill(&self, res: GridResolution) -> Vec<H3Index> {
+        let mut cells: Vec<H3Index> = self.0.iter().flat_map(|p| polyfill_cells(p, res)).collect();
+        cells.sort();
+        cells.dedup();
+        cells
+    }
+
+    fn polyfill_size(&self, res: GridResolution) -> usize {
+        self.0.iter().map(|p| p.polyfill_size(res)).sum()
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.0.iter().try_for_each(|p| p.validate())
+    }
+}
+
+/// Brings coordinates that have drifted out of range (e.g., through rounding
+/// in external data) back into valid latitudes and longitudes.
+pub trait ClampToValid {
+    /// Clamps latitudes to [-90, 90] and wraps longitudes into [-180, 180].
+    fn clamp_to_valid(&self) -> Polygon<f64>;
+}
+
+/// Clamps the coordinate's latitude and wraps its longitude.
+fn clamp_coordinate(c: Coordinate<f64>) -> Coordinate<f64> {
+    let x = if (-180. ..=180.).contains(&c.x) {
+        c.x
+    } else {
+        (c.x + 180.).rem_euclid(360.) - 180.
+    };
+    Coordinate {
+        x,
+        y: c.y.clamp(-90., 90.),
+    }
+}
+
+fn clamp_ring(ring: &LineString<f64>) -> LineString<f64> {
+    ring.0.iter().map(|c| clamp_coordinate(*c)).collect()
+}
+
+impl ClampToValid for Polygon<f64> {
+    fn clamp_to_valid(&self) -> Polygon<f64> {
+        Polygon::new(
+            clamp_ring(self.exterior()),
+            self.interiors().iter().map(clamp_ring).collect(),
+        )
+    }
+}
+
+/// Returns H3Index's covering the polygon as in `try_polyfill`, after clamping
+/// its coordinates into valid ranges.
+pub fn try_polyfill_clamped(p: &Polygon<f64>, res: GridResolution) -> Result<Vec<H3Index>> {
+    p.clamp_to_valid().try_polyfill(res)
+}
+
+/// Returns H3Index's covering the polygon, each paired with whether it lies on
+/// the boundary of the covering (i.e., has a neighbor outside of it).
+pub fn polyfill_classified(p: &Polygon<f64>, res: GridResolution) -> Vec<(H3Index, bool)> {
+    let cells: HashSet<H3Index> = polyfill_cells(p, res).into_iter().collect();
+    let mut result: Vec<(H3Index, bool)> = cells
+        .iter()
+        .map(|cell| (cell.clone(), on_covering_boundary(cell, &cells)))
+        .collect();
+    result.sort();
+    result
+}
+
+/// Does the cell have a neighbor outside of the covering?
+pub(crate) fn on_covering_boundary(cell: &H3Index, cells: &HashSet<H3Index>) -> bool {
+    cell.k_ring_indices(1).iter().any(|n| !cells.contains(n))
+}
+
+/// Returns H3Index's covering the polygon, sorted by centroid latitude and then
+/// longitude (i.e., in scanline order from south to north), for walking the
+/// covering row-by-row.
+pub fn polyfill_sorted(p: &Polygon<f64>, res: GridResolution) -> Vec<H3Index> {
+    let mut cells: Vec<(f64, f64, H3Index)> = polyfill_cells(p, res)
+        .into_iter()
+        .map(|i| {
+            let c = i.centroid();
+            (c.lat(), c.lng(), i)
+        })
+        .collect();
+    cells.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    cells.into_iter().map(|(_, _, i)| i).collect()
+}
+
+/// Returns the finest resolution at which the polygon's covering has at most
+/// max_cells indexes, or Z0 if none does. Since coverings grow about
+/// sevenfold per resolution, this steps from coarse to fine and stops at the
+/// first resolution over budget, so it never fills far past the budget.
+pub fn resolution_under_budget(p: &Polygon<f64>, max_cells: usize) -> GridResolution {
+    let mut best = GridResolution::Z0;
+    for res in GridResolution::iter() {
+        let count = polyfill_cells(p, res).len();
+        if count > max_cells {
+            break;
+        }
+        best = res;
+    }
+    best
+}
+
+/// Returns H3Index's covering the polygon, bucketed by base cell (e.g., for
+/// sharding). Each bucket is sorted.
+pub fn polyfill_by_base_cell(p: &Polygon<f64>, res: GridResolution) -> HashMap<i32, Vec<H3Index>> {
+    let mut buckets: HashMap<i32, Vec<H3Index>> = HashMap::new();
+    for cell in polyfill_cells(p, res) {
+        buckets.entry(cell.base_cell()).or_default().push(cell);
+    }
+    for cells in buckets.values_mut() {
+        cells.sort();
+    }
+    buckets
 }
 
+/// Returns the distinct parent_res cells containing at least one cell of the
+/// polygon's child_res covering, sorted. These are the coarse cells known to
+/// contain data at the finer resolution. Returns nothing if parent_res is
+/// finer than child_res.
+pub fn covering_parents(
+    p: &Polygon<f64>,
+    parent_res: GridResolution,
+    child_res: GridResolution,
+) -> Vec<H3Index> {
+    let mut parents: Vec<H3Index> = polyfill_cells(p, child_res)
+        .into_iter()
+        .map(|i| i.parent(parent_res))
+        .filter(|i| *i != H3Index(0))
+        .collect();
+    parents.sort();
+    parents.dedup();
+    parents
+}
+
+/// Returns an order-independent fingerprint of the covering, computed as the
+/// 64-bit FNV-1a hash of its sorted, de-duplicated indexes.
+pub fn covering_fingerprint(cells: &[H3Index]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+    let mut sorted: Vec<u64> = cells.iter().map(|i| i.0).collect();
+    sorted.sort();
+    sorted.dedup();
+    sorted
+        .iter()
+        .flat_map(|i| i.to_le_bytes().to_vec())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// Total area in square meters covered by the given indexes, summing each
+/// cell's exact area. Invalid indexes, such as polyfill padding, are skipped.
+pub fn total_area_m2(cells: &[H3Index]) -> f64 {
+    cells
+        .iter()
+        .filter(|i| i.is_valid())
+        .map(H3Index::area_m2)
+        .sum()
+}
+
+/// Spherical area of the ring in square meters (Chamberlain and Duquette).
+fn ring_area_m2(ring: &LineString<f64>) -> f64 {
+    let radius = METERS_PER_DEGREE.to_degrees();
+    let sum: f64 = ring
+        .lines()
+        .map(|l| {
+            let (lng1, lat1) = (l.start.x.to_radians(), l.start.y.to_radians());
+            let (lng2, lat2) = (l.end.x.to_radians(), l.end.y.to_radians());
+            (lng2 - lng1) * (2. + lat1.sin() + lat2.sin())
+        })
+        .sum();
+    (sum * radius * radius / 2.).abs()
+}
+
+/// Ratio of the area of the cells covering the polygon at the resolution to
+/// the polygon's own area. Values far from 1.0 suggest a finer resolution.
+/// Cell areas are computed as in `total_area_m2`.
+pub fn fill_ratio(p: &Polygon<f64>, res: GridResolution) -> f64 {
+    let cells = polyfill_cells(p, res);
+    let area = ring_area_m2(p.exterior()) - p.interiors().iter().map(ring_area_m2).sum::<f64>();
+    total_area_m2(&cells) / area
+}
+
+/// Reads a GeoJSON FeatureCollection, returning each feature's id paired with
+/// the H3Index's covering its (Multi)Polygon geometry. Features without a
+/// geometry map to an empty covering. A bare Feature is read as a collection
+/// of one; a bare Geometry is an error.
+pub fn index_geojson_features<R: Read>(
+    mut r: R,
+    res: GridResolution,
+) -> Result<Vec<(Option<String>, Vec<H3Index>)>> {
+    let mut buf = String::new();
+    r.read_to_string(&mut buf)?;
+    let features = match buf.parse::<GeoJson>()? {
+        GeoJson::FeatureCollection(collection) => collection.features,
+        GeoJson::Feature(feature) => vec![feature],
+        GeoJson::Geometry(_) => {
+            return Err(Error::GeoJsonError(geojson::Error::ExpectedType {
+                expected: "FeatureCollection or Feature".to_string(),
+                actual: "Geometry".to_string(),
+            }))
+        }
+    };
+    let mut result = Vec::new();
+    for feature in features {
+        let id = feature.id.map(|id| match id {
+            Id::String(s) => s,
+            Id::Number(n) => n.to_string(),
+        });
+        let polygons: MultiPolygon<f64> = match feature.geometry.map(|g| g.value) {
+            None => MultiPolygon(vec![]),
+            Some(value @ Value::MultiPolygon(_)) => value.try_into()?,
+            Some(value) => MultiPolygon(vec![value.try_into()?]),
+        };
+        result.push((id, polygons.try_polyfill(res)?));
+    }
+    Ok(result)
+}
+
+/// Compacts the cells and returns a FeatureCollection with one Polygon feature
+/// per compacted cell, identified by its hex index and tagged with its
+/// `resolution` property, so that multi-resolution coverings render with
+/// varying cell sizes. Cells that can't be compacted (e.g., a set already
+/// spanning resolutions) are emitted as given.
+pub fn compacted_to_feature_collection(cells: &[H3Index]) -> FeatureCollection {
+    let compacted = cells.to_vec().compact().unwrap_or_else(|_| cells.to_vec());
+    let features = compacted
+        .into_iter()
+        .filter(|i| *i != H3Index(0))
+        .map(|cell| {
+            let boundary = Polygon::new(LineString::from(cell.clone()), vec![]);
+            let properties = cell
+                .resolution()
+                .map(|res| ("resolution".to_string(), (res as i32).into()))
+                .into_iter()
+                .collect();
+            Feature {
+                bbox: None,
+                geometry: Some(Geometry::new(Value::from(&boundary))),
+                id: Some(Id::String(format!("{:x}", cell.0))),
+                properties: Some(properties),
+                foreign_members: None,
+            }
+        })
+        .collect();
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+}
+
+/// Returns a FeatureCollection with one Polygon feature per cell, identified
+/// by its hex index and with its value stored under the `value_key` property
+/// (e.g., for choropleth maps). Values that fail to serialize are stored as
+/// null.
+#[cfg(feature = "serde")]
+pub fn cells_with_values_to_geojson<V: serde::Serialize>(
+    pairs: &[(H3Index, V)],
+    value_key: &str,
+) -> FeatureCollection {
+    let features = pairs
+        .iter()
+        .filter(|(cell, _)| *cell != H3Index(0))
+        .map(|(cell, value)| {
+            let boundary = Polygon::new(LineString::from(cell.clone()), vec![]);
+            let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+            Feature {
+                bbox: None,
+                geometry: Some(Geometry::new(Value::from(&boundary))),
+                id: Some(Id::String(format!("{:x}", cell.0))),
+                properties: Some(std::iter::once((value_key.to_string(), value)).collect()),
+                foreign_members: None,
+            }
+        })
+        .collect();
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+}
+
+/// Even-odd test of whether the point lies within the (closed) ring.
+pub(crate) fn ring_contains(ring: &LineString<f64>, p: Coordinate<f64>) -> bool {
+    let mut inside = false;
+    for line in ring.lines() {
+        let (a, b) = (line.start, line.end);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Planar (shoelace) area of the ring, in square degrees.
+fn ring_area(ring: &LineString<f64>) -> f64 {
+    ring.lines()
+        .map(|l| l.start.x * l.end.y - l.end.x * l.start.y)
+        .sum::<f64>()
+        .abs()
+        / 2.
+}
+
+/// Returns a simplified polygon enclosing the largest connected part of the
+/// set: the exterior of its dissolved outline, without holes, simplified to
+/// the tolerance (Douglas-Peucker). The tolerance is reduced as needed so that
+/// the polygon still contains the centroids of the cells within the outline.
+/// Returns error if the set can't be dissolved (e.g., it's empty).
+pub fn enclosing_polygon(cells: &[H3Index], tolerance_m: f64) -> Result<Polygon<f64>> {
+    let dissolved = ToMultiPolygon(cells.to_vec());
+    let largest = dissolved
+        .0
+        .into_iter()
+        .max_by(|a, b| {
+            ring_area(a.exterior())
+                .partial_cmp(&ring_area(b.exterior()))
+                .unwrap()
+        })
+        .ok_or_else(|| Error::UnableToDissolve(cells.to_vec()))?;
+    let exterior = largest.exterior().clone();
+    let centroids: Vec<Coordinate<f64>> = cells
+        .iter()
+        .map(|c| Coordinate::from(c.centroid()))
+        .filter(|c| ring_contains(&exterior, *c))
+        .collect();
+    let mut tolerance = tolerance_m / METERS_PER_DEGREE;
+    while tolerance > f64::EPSILON {
+        let simplified = Polygon::new(simplify_ring(&exterior, tolerance), vec![]);
+        if centroids
+            .iter()
+            .all(|c| ring_contains(simplified.exterior(), *c))
+        {
+            return Ok(simplified);
+        }
+        tolerance /= 2.;
+    }
+    Ok(Polygon::new(exterior, vec![]))
+}
+
+/// Dissolves the indexes (all at the same resolution) into the polygons
+/// outlining the set, with holes where the set has gaps.
 // TODO(mookerji): From<Vec<H3Index>>?
+#[allow(non_snake_case)]
 pub fn ToMultiPolygon(indices: Vec<H3Index>) -> MultiPolygon<f64> {
-    // h3_sys::h3SetToLinkedGeo
-    // h3_sys::destroyLinkedPolygon
-    MultiPolygon(vec![])
+    let set: Vec<H3Index> = indices.into_iter().filter(|i| *i != H3Index(0)).collect();
+    let mut linked = h3_sys::LinkedGeoPolygon::default();
+    let mut polygons = Vec::new();
+    unsafe {
+        h3_sys::h3SetToLinkedGeo(
+            set.as_ptr() as *const h3_sys::H3Index,
+            set.len() as i32,
+            &mut linked,
+        );
+        // Each polygon's first loop is its exterior; the rest are holes.
+        let mut polygon: *const h3_sys::LinkedGeoPolygon = &linked;
+        while !polygon.is_null() && !(*polygon).first.is_null() {
+            let mut rings: Vec<LineString<f64>> = Vec::new();
+            let mut geo_loop = (*polygon).first;
+            while !geo_loop.is_null() {
+                let mut coords: Vec<Coordinate<f64>> = Vec::new();
+                let mut coord = (*geo_loop).first;
+                while !coord.is_null() {
+                    coords.push(GeoCoord((*coord).vertex).into());
+                    coord = (*coord).next;
+                }
+                rings.push(coords.into());
+                geo_loop = (*geo_loop).next;
+            }
+            let exterior = rings.remove(0);
+            polygons.push(Polygon::new(exterior, rings));
+            polygon = (*polygon).next;
+        }
+        h3_sys::destroyLinkedPolygon(&mut linked);
+    }
+    MultiPolygon(polygons)
+}
+
+/// The outlines of a set of cells with their shared boundaries stored once, in
+/// the manner of TopoJSON.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SharedArcs {
+    /// Maximal runs of boundary shared by the same cells (or by a single
+    /// cell, along the outside of the set).
+    pub arcs: Vec<LineString<f64>>,
+    /// Each cell with its outline as references into `arcs`: `i` for arc i
+    /// as stored, and `!i` (i.e., `-i - 1`) for arc i reversed.
+    pub polygons: Vec<(H3Index, Vec<i64>)>,
+}
+
+/// Returns the outlines of the cells (all at the same resolution) as a list of
+/// arcs, with each boundary shared by two cells appearing once.
+pub fn cells_to_shared_arcs(cells: &[H3Index]) -> SharedArcs {
+    let mut cells: Vec<H3Index> = cells.iter().filter(|i| i.is_valid()).cloned().collect();
+    cells.sort();
+    cells.dedup();
+    let tolerance = match cells.first().and_then(|c| c.resolution()) {
+        Some(res) => res.coordinate_tolerance(),
+        None => {
+            return SharedArcs {
+                arcs: vec![],
+                polygons: vec![],
+            }
+        }
+    };
+
+    // Identify vertices shared between cells, up to the tolerance.
+    let mut vertex_ids: HashMap<(i64, i64), usize> = HashMap::new();
+    let mut vertices: Vec<Coordinate<f64>> = Vec::new();
+    let rings: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|cell| {
+            LineString::from(cell.clone())
+                .0
+                .into_iter()
+                .map(|c| {
+                    let key = (
+                        (c.x / tolerance).round() as i64,
+                        (c.y / tolerance).round() as i64,
+                    );
+                    *vertex_ids.entry(key).or_insert_with(|| {
+                        vertices.push(c);
+                        vertices.len() - 1
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    let segment = |a: usize, b: usize| (a.min(b), a.max(b));
+    let mut owners: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (cell, ring) in rings.iter().enumerate() {
+        for (i, a) in ring.iter().enumerate() {
+            let b = ring[(i + 1) % ring.len()];
+            owners.entry(segment(*a, b)).or_default().push(cell);
+        }
+    }
+
+    let mut arcs: Vec<LineString<f64>> = Vec::new();
+    let mut arc_ids: HashMap<Vec<usize>, i64> = HashMap::new();
+    let mut polygons = Vec::with_capacity(cells.len());
+    for (cell, ring) in cells.into_iter().zip(rings) {
+        let n = ring.len();
+        let owners_of = |i: usize| &owners[&segment(ring[i % n], ring[(i + 1) % n])];
+        // Start at a change of owners, so that no run wraps around the ring.
+        let start = (0..n)
+            .find(|i| owners_of(*i) != owners_of(*i + n - 1))
+            .unwrap_or(0);
+        let mut refs = Vec::new();
+        let mut run = vec![ring[start]];
+        for i in start..start + n {
+            run.push(ring[(i + 1) % n]);
+            if i + 1 == start + n || owners_of(i) != owners_of(i + 1) {
+                let reversed: Vec<usize> = run.iter().rev().cloned().collect();
+                let id = match arc_ids.get(&reversed) {
+                    Some(id) => !*id,
+                    None => *arc_ids.entry(run.clone()).or_insert_with(|| {
+                        arcs.push(run.iter().map(|v| vertices[*v]).collect());
+                        arcs.len() as i64 - 1
+                    }),
+                };
+                refs.push(id);
+                run = vec![ring[(i + 1) % n]];
+            }
+        }
+        polygons.push((cell, refs));
+    }
+    SharedArcs { arcs, polygons }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use geo_types::{line_string, polygon, Point};
+    use crate::measure::point_distance_m;
+    use geo_types::{line_string, polygon};
+
+    /// San Francisco, as in the h3 test suites.
+    pub(crate) fn sf_polygon() -> Polygon<f64> {
+        polygon![
+            (x: -122.4089867, y: 37.8133190),
+            (x: -122.3805437, y: 37.7866302),
+            (x: -122.3544737, y: 37.7198062),
+            (x: -122.5123437, y: 37.7076132),
+            (x: -122.5247187, y: 37.7835872),
+            (x: -122.4798767, y: 37.8151572),
+        ]
+    }
 
     fn assert_approx_point(expected: Point<f64>, actual: Point<f64>, eps: f64) {
         assert_relative_eq!(actual.lat(), expected.lat(), epsilon = eps);
@@ -97,10 +743,36 @@ mod tests {
         let actual_vec = actual.into_points();
         let expected_vec = expected.into_points();
         for i in 0..actual_vec.len() {
-            assert_approx_point(actual_vec[i], expected_vec[i], 1.0e-9);
+            assert_approx_point(
+                actual_vec[i],
+                expected_vec[i],
+                GridResolution::Z5.coordinate_tolerance(),
+            );
         }
     }
 
+    #[test]
+    fn test_geometry() {
+        let cell = H3Index(0x85283473fffffff);
+        let (centroid, boundary) = cell.geometry();
+        assert_eq!(centroid, cell.centroid());
+        assert_eq!(boundary, LineString::from(cell));
+        assert!(boundary.num_coords() >= 6);
+        // Even-odd ray casting from the centroid over the closed ring.
+        let ring = Polygon::new(boundary, vec![]).exterior().clone();
+        let mut inside = false;
+        for line in ring.lines() {
+            let (a, b) = (line.start, line.end);
+            if (a.y > centroid.lat()) != (b.y > centroid.lat()) {
+                let x = a.x + (centroid.lat() - a.y) / (b.y - a.y) * (b.x - a.x);
+                if centroid.lng() < x {
+                    inside = !inside;
+                }
+            }
+        }
+        assert!(inside);
+    }
+
     #[test]
     fn test_polyfill() {
         let poly = polygon![
@@ -121,6 +793,163 @@ mod tests {
         assert_eq!(indices.len(), max_indices);
     }
 
+    #[test]
+    fn test_polyfill_sorted() {
+        let poly = sf_polygon();
+        let res = GridResolution::Z8;
+        let sorted = polyfill_sorted(&poly, res);
+        let num_cells = poly
+            .polyfill(res)
+            .iter()
+            .filter(|i| **i != H3Index(0))
+            .count();
+        assert_eq!(sorted.len(), num_cells);
+        let keys: Vec<(f64, f64)> = sorted
+            .iter()
+            .map(|i| (i.centroid().lat(), i.centroid().lng()))
+            .collect();
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_cells_with_values_to_geojson() {
+        let pairs: Vec<(H3Index, f64)> = H3Index(0x8928308280fffff)
+            .k_ring_indices(1)
+            .into_iter()
+            .enumerate()
+            .map(|(i, cell)| (cell, i as f64 * 0.5))
+            .collect();
+        let collection = cells_with_values_to_geojson(&pairs, "density");
+        assert_eq!(collection.features.len(), pairs.len());
+        for (feature, (cell, value)) in collection.features.iter().zip(&pairs) {
+            assert_eq!(feature.id, Some(Id::String(format!("{:x}", cell.0))));
+            let properties = feature.properties.as_ref().unwrap();
+            assert_eq!(properties["density"].as_f64(), Some(*value));
+        }
+    }
+
+    #[test]
+    fn test_compacted_to_feature_collection() {
+        let parent = H3Index(0x8828308281fffff);
+        let mut cells = parent.children(GridResolution::Z9);
+        let sibling = parent.k_ring_indices(1)[1].clone();
+        let sibling_child = sibling.children_ordered(GridResolution::Z9)[0].clone();
+        cells.push(sibling_child.clone());
+        let collection = compacted_to_feature_collection(&cells);
+        assert_eq!(collection.features.len(), 2);
+        let mut resolutions: Vec<i64> = collection
+            .features
+            .iter()
+            .map(|f| {
+                f.properties.as_ref().unwrap()["resolution"]
+                    .as_i64()
+                    .unwrap()
+            })
+            .collect();
+        resolutions.sort();
+        assert_eq!(resolutions, vec![8, 9]);
+        let ids: Vec<Id> = collection
+            .features
+            .iter()
+            .map(|f| f.id.clone().unwrap())
+            .collect();
+        assert!(ids.contains(&Id::String("8828308281fffff".to_string())));
+        assert!(collection
+            .features
+            .iter()
+            .all(|f| matches!(f.geometry.as_ref().unwrap().value, Value::Polygon(_))));
+    }
+
+    #[test]
+    fn test_to_multi_polygon() {
+        let origin = H3Index(0x8928308280fffff);
+        let mut cells = origin.k_ring_indices(2);
+        cells.retain(|c| *c != origin);
+        let dissolved = ToMultiPolygon(cells.clone());
+        assert_eq!(dissolved.0.len(), 1);
+        assert_eq!(dissolved.0[0].interiors().len(), 1);
+        let far = origin.hex_ring(5).unwrap()[0].clone();
+        cells.push(far);
+        assert_eq!(ToMultiPolygon(cells).0.len(), 2);
+        assert!(ToMultiPolygon(vec![]).0.is_empty());
+    }
+
+    #[test]
+    fn test_enclosing_polygon() {
+        let cells = H3Index(0x8928308280fffff).k_ring_indices(4);
+        let exact = ToMultiPolygon(cells.clone()).0[0].exterior().num_coords();
+        let polygon = enclosing_polygon(&cells, 300.).unwrap();
+        assert!(polygon.exterior().num_coords() < exact);
+        for cell in &cells {
+            let c = Coordinate::from(cell.centroid());
+            assert!(ring_contains(polygon.exterior(), c));
+        }
+        assert_eq!(
+            enclosing_polygon(&[], 300.),
+            Err(Error::UnableToDissolve(vec![]))
+        );
+    }
+
+    #[test]
+    fn test_covering_parents() {
+        let square = polygon![
+            (x: -122.5, y: 37.7),
+            (x: -122.4, y: 37.7),
+            (x: -122.4, y: 37.8),
+            (x: -122.5, y: 37.8),
+        ];
+        let (parent_res, child_res) = (GridResolution::Z6, GridResolution::Z9);
+        let parents = covering_parents(&square, parent_res, child_res);
+        assert!(!parents.is_empty());
+        assert!(parents.windows(2).all(|w| w[0] < w[1]));
+        let fine: Vec<H3Index> = square
+            .polyfill(child_res)
+            .into_iter()
+            .filter(|i| *i != H3Index(0))
+            .collect();
+        for parent in &parents {
+            assert_eq!(parent.resolution(), Some(parent_res));
+            assert!(fine.iter().any(|cell| parent.contains_cell(cell)));
+        }
+        for cell in &fine {
+            assert!(parents.contains(&cell.parent(parent_res)));
+        }
+        assert!(covering_parents(&square, child_res, parent_res).is_empty());
+    }
+
+    #[test]
+    fn test_polyfill_by_base_cell() {
+        // Spans several base cells in the western US.
+        let poly = polygon![
+            (x: -130., y: 25.),
+            (x: -100., y: 25.),
+            (x: -100., y: 50.),
+            (x: -130., y: 50.),
+        ];
+        let res = GridResolution::Z3;
+        let buckets = polyfill_by_base_cell(&poly, res);
+        assert!(buckets.len() > 1);
+        for (base_cell, cells) in &buckets {
+            assert!(cells.iter().all(|c| c.base_cell() == *base_cell));
+        }
+        let num_cells = poly
+            .polyfill(res)
+            .iter()
+            .filter(|i| **i != H3Index(0))
+            .count();
+        assert_eq!(buckets.values().map(|b| b.len()).sum::<usize>(), num_cells);
+    }
+
+    #[test]
+    fn test_fill_ratio() {
+        let poly = sf_polygon();
+        let coarse = fill_ratio(&poly, GridResolution::Z6);
+        let fine = fill_ratio(&poly, GridResolution::Z10);
+        assert!((fine - 1.).abs() < (coarse - 1.).abs());
+        assert!((fine - 1.).abs() < 0.05);
+    }
+
     #[test]
     fn test_polyfill_with_hole() {
         let poly = polygon!(
@@ -146,6 +975,33 @@ mod tests {
         assert_eq!(indices.len(), max_indices);
     }
 
+    #[test]
+    fn test_polyfill_does_not_leak() {
+        use crate::alloc_counter::LIVE_BYTES;
+        let poly = polygon!(
+            exterior: [
+                (x: -122.4089867, y: 37.8133190),
+                (x: -122.3544737, y: 37.7198062),
+                (x: -122.5123437, y: 37.7076132),
+            ],
+            interiors: [
+                [
+                    (x: -122.4471197, y: 37.7869802),
+                    (x: -122.4590777, y: 37.7664102),
+                    (x: -122.4137097, y: 37.7710682)
+                ]
+            ],
+        );
+        let res = GridResolution::Z7;
+        poly.polyfill(res);
+        let before = LIVE_BYTES.with(|n| n.get());
+        for _ in 0..100 {
+            poly.polyfill(res);
+            poly.try_polyfill(res).unwrap();
+        }
+        assert_eq!(LIVE_BYTES.with(|n| n.get()), before);
+    }
+
     #[test]
     fn test_polyfill_with_two_holes() {
         let poly = polygon!(
@@ -170,7 +1026,37 @@ mod tests {
                 ],
             ]
         );
-        // TODO: if holes are identical, test crashes?
+        let res = GridResolution::Z9;
+        let indices = poly.polyfill(res);
+        assert!(indices.len() > 1000);
+        let max_indices = poly.polyfill_size(res);
+        assert_eq!(indices.len(), max_indices);
+    }
+
+    #[test]
+    fn test_polyfill_with_identical_holes() {
+        let poly = polygon!(
+            exterior: [
+                (x: -122.4089866, y: 37.8133189),
+                (x: -122.3805436, y: 37.7866302),
+                (x: -122.3544736, y: 37.7198061),
+                (x: -122.5123436, y: 37.7076131),
+                (x: -122.5247187, y: 37.7835871),
+                (x: -122.4798767, y: 37.8151571)
+            ],
+            interiors: [
+                [
+                    (x: -122.4471197, y: 37.7869802),
+                    (x: -122.4590777, y: 37.7664102),
+                    (x: -122.4137097, y: 37.7710682)
+                ],
+                [
+                    (x: -122.4471197, y: 37.7869802),
+                    (x: -122.4590777, y: 37.7664102),
+                    (x: -122.4137097, y: 37.7710682)
+                ],
+            ]
+        );
         let res = GridResolution::Z9;
         let indices = poly.polyfill(res);
         assert!(indices.len() > 1000);
@@ -242,6 +1128,226 @@ mod tests {
         assert_eq!(indices.len(), max_indices);
     }
 
+    #[test]
+    fn test_polyfill_self_intersecting() {
+        let bowtie = polygon![
+            (x: -122.5, y: 37.7),
+            (x: -122.4, y: 37.8),
+            (x: -122.4, y: 37.7),
+            (x: -122.5, y: 37.8),
+        ];
+        assert_eq!(
+            bowtie.try_polyfill(GridResolution::Z9),
+            Err(Error::InvalidPolygon(bowtie.clone()))
+        );
+        let square = polygon![
+            (x: -122.5, y: 37.7),
+            (x: -122.4, y: 37.7),
+            (x: -122.4, y: 37.8),
+            (x: -122.5, y: 37.8),
+        ];
+        assert!(square.try_polyfill(GridResolution::Z9).is_ok());
+        let line = polygon![(x: -122.5, y: 37.7), (x: -122.4, y: 37.8)];
+        assert!(line.try_polyfill(GridResolution::Z9).is_err());
+    }
+
+    #[test]
+    fn test_polyfill_nan_vertex() {
+        let poly = polygon![
+            exterior: [
+                (x: -122.5, y: 37.7),
+                (x: f64::NAN, y: 37.7),
+                (x: -122.4, y: 37.8),
+                (x: -122.5, y: 37.8),
+            ],
+            interiors: [[
+                (x: -122.46, y: 37.74),
+                (x: -122.44, y: f64::NAN),
+                (x: -122.44, y: 37.76),
+            ]],
+        ];
+        // Converting the polygon for h3 mustn't panic.
+        poly.polyfill(GridResolution::Z7);
+        assert!(poly.try_polyfill(GridResolution::Z7).is_err());
+    }
+
+    #[test]
+    fn test_index_geojson_features() {
+        let input = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "id": "presidio",
+                    "properties": {},
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[
+                            [-122.48, 37.79], [-122.45, 37.79],
+                            [-122.45, 37.81], [-122.48, 37.81],
+                            [-122.48, 37.79]
+                        ]]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "properties": {},
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[
+                            [-122.42, 37.76], [-122.40, 37.76],
+                            [-122.40, 37.78], [-122.42, 37.78],
+                            [-122.42, 37.76]
+                        ]]
+                    }
+                }
+            ]
+        }"#;
+        let features = index_geojson_features(input.as_bytes(), GridResolution::Z9).unwrap();
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].0, Some("presidio".to_string()));
+        assert_eq!(features[1].0, None);
+        assert!(!features[0].1.is_empty());
+        assert!(!features[1].1.is_empty());
+        for cell in &features[0].1 {
+            assert!(!features[1].1.contains(cell));
+        }
+        assert!(index_geojson_features("{}".as_bytes(), GridResolution::Z9).is_err());
+
+        let value: serde_json::Value = serde_json::from_str(input).unwrap();
+        let feature = value["features"][0].to_string();
+        let single = index_geojson_features(feature.as_bytes(), GridResolution::Z9).unwrap();
+        assert_eq!(single, features[..1].to_vec());
+        let geometry = value["features"][0]["geometry"].to_string();
+        match index_geojson_features(geometry.as_bytes(), GridResolution::Z9) {
+            Err(Error::GeoJsonError(geojson::Error::ExpectedType { actual, .. })) => {
+                assert_eq!(actual, "Geometry")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_polyfill_classified() {
+        let square = polygon![
+            (x: -122.5, y: 37.7),
+            (x: -122.4, y: 37.7),
+            (x: -122.4, y: 37.8),
+            (x: -122.5, y: 37.8),
+        ];
+        let cells = polyfill_classified(&square, GridResolution::Z8);
+        let boundary: Vec<&H3Index> = cells.iter().filter(|c| c.1).map(|c| &c.0).collect();
+        let interior: Vec<&H3Index> = cells.iter().filter(|c| !c.1).map(|c| &c.0).collect();
+        assert!(!boundary.is_empty());
+        assert!(interior.len() > boundary.len());
+        // Interior cells are surrounded by other cells in the covering.
+        for cell in interior {
+            for neighbor in cell.k_ring_indices(1) {
+                assert!(cells.iter().any(|c| c.0 == neighbor));
+            }
+        }
+        for cell in boundary {
+            let neighbors = cell.k_ring_indices(1);
+            assert!(neighbors.iter().any(|n| !cells.iter().any(|c| c.0 == *n)));
+        }
+    }
+
+    #[test]
+    fn test_multi_polygon_polyfill() {
+        let west = polygon![
+            (x: -122.5, y: 37.7),
+            (x: -122.4, y: 37.7),
+            (x: -122.4, y: 37.8),
+            (x: -122.5, y: 37.8),
+        ];
+        let east = polygon![
+            (x: -122.4, y: 37.7),
+            (x: -122.3, y: 37.7),
+            (x: -122.3, y: 37.8),
+            (x: -122.4, y: 37.8),
+        ];
+        let res = GridResolution::Z9;
+        let cells = |p: &Polygon<f64>| -> HashSet<H3Index> {
+            p.polyfill(res)
+                .into_iter()
+                .filter(|i| *i != H3Index(0))
+                .collect()
+        };
+        let (west_cells, east_cells) = (cells(&west), cells(&east));
+        let overlap = west_cells.intersection(&east_cells).count();
+        let multi = MultiPolygon(vec![west, east]);
+        let union = multi.polyfill(res);
+        assert_eq!(union.len(), west_cells.len() + east_cells.len() - overlap);
+        assert!(union.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(multi.try_polyfill(res), Ok(union));
+        assert!(MultiPolygon(vec![]).polyfill(res).is_empty());
+    }
+
+    #[test]
+    fn test_default_try_polyfill() {
+        // A region implementing only the required methods.
+        struct Cell(H3Index);
+        impl ToH3Region for Cell {
+            fn polyfill(&self, res: GridResolution) -> Vec<H3Index> {
+                self.0.children_ordered(res)
+            }
+
+            fn polyfill_size(&self, res: GridResolution) -> usize {
+                self.0.max_children(res)
+            }
+        }
+        let cell = Cell(H3Index(0x87283472bffffff));
+        let res = GridResolution::Z8;
+        assert_eq!(cell.try_polyfill(res), Ok(cell.polyfill(res)));
+    }
+
+    #[test]
+    fn test_covering_fingerprint() {
+        let cells = H3Index(0x8928308280fffff).k_ring_indices(2);
+        let mut shuffled = cells.clone();
+        shuffled.reverse();
+        shuffled.swap(0, 7);
+        shuffled.push(cells[3].clone());
+        assert_eq!(
+            covering_fingerprint(&cells),
+            covering_fingerprint(&shuffled)
+        );
+        assert_ne!(
+            covering_fingerprint(&cells),
+            covering_fingerprint(&cells[1..])
+        );
+    }
+
+    #[test]
+    fn test_total_area_m2() {
+        let poly = sf_polygon();
+        // Equirectangular approximation of the polygon's area, which is
+        // accurate enough at this scale.
+        let lat0 = 37.76_f64.to_radians();
+        let m_per_deg = 6_371_007.18 * std::f64::consts::PI / 180.;
+        let points = poly.exterior().clone().into_points();
+        let mut twice_area = 0_f64;
+        for i in 0..points.len() {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            twice_area += a.lng() * b.lat() - b.lng() * a.lat();
+        }
+        let expected = (twice_area / 2.).abs() * m_per_deg * m_per_deg * lat0.cos();
+        let res = GridResolution::Z9;
+        let cells: Vec<H3Index> = poly
+            .polyfill(res)
+            .into_iter()
+            .filter(|i| *i != H3Index(0))
+            .collect();
+        let actual = total_area_m2(&cells);
+        assert!((actual - expected).abs() / expected < 0.1);
+        // Each cell contributes its exact area, not the resolution's average.
+        let exact: f64 = cells.iter().map(H3Index::area_m2).sum();
+        assert_relative_eq!(actual, exact, max_relative = 1e-9);
+        assert_ne!(actual, cells.len() as f64 * res.hex_area());
+        assert_eq!(total_area_m2(&cells[..1]), cells[0].area_m2());
+        assert_eq!(total_area_m2(&[H3Index(0)]), 0.);
+    }
+
     // #[test]
     // fn test_h3_set_to_multi_polygon_empty() {
     //     let multipolygon = ToMultiPolygon(vec![]);
@@ -253,4 +1359,131 @@ mod tests {
     //     let multipolygon = ToMultiPolygon(vec![]);
     //     assert!(false);
     // }
+
+    #[test]
+    fn test_to_circle_approx() {
+        let cell = H3Index(0x85283473fffffff);
+        let center = cell.centroid();
+        let circumradius = LineString::from(cell.clone())
+            .points_iter()
+            .map(|p| (p.lng() - center.lng()).hypot(p.lat() - center.lat()))
+            .fold(0f64, f64::max);
+        let circle = cell.to_circle_approx(16);
+        // The ring is closed, so skip the repeated first vertex.
+        let vertices: Vec<Point<f64>> = circle.exterior().points_iter().skip(1).collect();
+        assert_eq!(vertices.len(), 16);
+        let n = vertices.len() as f64;
+        let mean = Point::new(
+            vertices.iter().map(|p| p.lng()).sum::<f64>() / n,
+            vertices.iter().map(|p| p.lat()).sum::<f64>() / n,
+        );
+        assert_approx_point(center, mean, 1e-3);
+        for p in vertices {
+            assert!((p.lng() - center.lng()).hypot(p.lat() - center.lat()) < circumradius);
+        }
+    }
+
+    #[test]
+    fn test_clamp_to_valid() {
+        let p = Polygon::new(
+            LineString::from(vec![
+                (179.5, 0.),
+                (180.0000001, 0.),
+                (180.0000001, 0.5),
+                (179.5, 0.5),
+                (179.5, 0.),
+            ]),
+            vec![],
+        );
+        let clamped = p.clamp_to_valid();
+        assert!(clamped
+            .exterior()
+            .points_iter()
+            .all(|c| c.lng().abs() <= 180. && c.lat().abs() <= 90.));
+        assert_relative_eq!(clamped.exterior().0[1].x, -179.9999999, epsilon = 1e-9);
+        let cells = try_polyfill_clamped(&p, GridResolution::Z5).unwrap();
+        assert!(cells.iter().any(|i| *i != H3Index(0)));
+        let pole = Polygon::new(
+            LineString::from(vec![(0., 89.), (10., 90.5), (20., 89.), (0., 89.)]),
+            vec![],
+        );
+        assert_eq!(pole.clamp_to_valid().exterior().0[1].y, 90.);
+    }
+
+    #[test]
+    fn test_edge_midpoints() {
+        let cell = H3Index(0x8928308280fffff);
+        let midpoints = cell.edge_midpoints();
+        assert_eq!(midpoints.len(), 6);
+        let vertices = LineString::from(cell).into_points();
+        let n = vertices.len();
+        for m in &midpoints {
+            // The midpoint splits some boundary edge into equal halves.
+            assert!((0..n).any(|i| {
+                let (a, b) = (&vertices[i], &vertices[(i + 1) % n]);
+                let (am, mb) = (point_distance_m(a, m), point_distance_m(m, b));
+                (am - mb).abs() < 1e-3 && (am + mb - point_distance_m(a, b)).abs() < 1e-3
+            }));
+        }
+        assert_eq!(H3Index(0x821c07fffffffff).edge_midpoints().len(), 5);
+    }
+
+    #[test]
+    fn test_resolution_under_budget() {
+        let p = H3Index(0x8928308280fffff)
+            .parent(GridResolution::Z6)
+            .to_circle_approx(12);
+        let count = |res| {
+            p.polyfill(res)
+                .into_iter()
+                .filter(|i| *i != H3Index(0))
+                .count()
+        };
+        let finer = |res: GridResolution| GridResolution::from_i32(res as i32 + 1).unwrap();
+        let tight = resolution_under_budget(&p, 10);
+        let generous = resolution_under_budget(&p, 10_000);
+        assert!(tight < generous);
+        assert!(count(tight) <= 10 && count(finer(tight)) > 10);
+        assert!(count(generous) <= 10_000 && count(finer(generous)) > 10_000);
+        // Coarse coverings of a small polygon may be empty.
+        assert_eq!(count(resolution_under_budget(&p, 0)), 0);
+    }
+
+    #[test]
+    fn test_cells_to_shared_arcs() {
+        let a = H3Index(0x891ea6d6533ffff);
+        let b = H3Index(0x891ea6d65afffff);
+        let shared = a.unidirectional_edge_to(&b).unwrap().boundary();
+        let topology = cells_to_shared_arcs(&[b.clone(), a.clone()]);
+        assert_eq!(topology.polygons.len(), 2);
+        // Each cell has its outside boundary plus the shared edge.
+        assert_eq!(topology.arcs.len(), 3);
+        let eps = GridResolution::Z9.coordinate_tolerance();
+        let matches_shared = |arc: &LineString<f64>| {
+            arc.num_coords() == shared.num_coords()
+                && arc.points_iter().all(|p| {
+                    shared
+                        .points_iter()
+                        .any(|q| (p.lat() - q.lat()).abs() < eps && (p.lng() - q.lng()).abs() < eps)
+                })
+        };
+        let shared_ids: Vec<i64> = (0..topology.arcs.len() as i64)
+            .filter(|i| matches_shared(&topology.arcs[*i as usize]))
+            .collect();
+        assert_eq!(shared_ids.len(), 1);
+        let id = shared_ids[0];
+        let (ref_a, ref_b) = (&topology.polygons[0].1, &topology.polygons[1].1);
+        assert_eq!(topology.polygons[0].0, a);
+        assert!(ref_a.contains(&id) && ref_b.contains(&!id));
+
+        // Each outline's arcs trace the cell boundary.
+        for (cell, refs) in &topology.polygons {
+            let num_coords: usize = refs
+                .iter()
+                .map(|r| topology.arcs[if *r < 0 { !*r } else { *r } as usize].num_coords() - 1)
+                .sum();
+            assert_eq!(num_coords, LineString::from(cell.clone()).num_coords());
+        }
+        assert!(cells_to_shared_arcs(&[]).arcs.is_empty());
+    }
 }
@@ -46,9 +46,11 @@ use crate::errors::*;
 use crate::types::*;
 
 use num_traits::FromPrimitive;
+use std::convert::TryFrom;
 
 /// H3 Grid Resolution
 #[allow(unused_variables)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Primitive)]
 pub enum GridResolution {
     Z0 = 0,
@@ -72,29 +74,117 @@ pub enum GridResolution {
 /// Maximum grid resolution
 pub const MAX_GRID_RESOLUTION: i32 = GridResolution::Z15 as i32;
 
+/// Length in meters of one degree along a great circle, using h3's mean Earth
+/// radius.
+pub(crate) const METERS_PER_DEGREE: f64 = 6_371_007.180_918_475 * std::f64::consts::PI / 180.;
+
 impl GridResolution {
+    /// Returns the resolution with the given number, if it's in [0, 15].
+    pub fn from_i32(i: i32) -> Option<GridResolution> {
+        <GridResolution as FromPrimitive>::from_i32(i)
+    }
+
+    /// Returns the resolution's number.
+    pub fn as_i32(self) -> i32 {
+        self as i32
+    }
+
+    /// Iterates over all resolutions, from Z0 to Z15.
+    pub fn iter() -> impl Iterator<Item = GridResolution> {
+        (0..=MAX_GRID_RESOLUTION).filter_map(GridResolution::from_i32)
+    }
+
     /// Average hexagon edge length in meters at the given resolution.
     pub fn edge_length(self) -> f64 {
         unsafe { h3_sys::edgeLengthM(self as i32) }
     }
 
+    /// Average hexagon edge length in kilometers at the given resolution.
+    pub fn edge_length_km(self) -> f64 {
+        unsafe { h3_sys::edgeLengthKm(self as i32) }
+    }
+
     /// Average hexagon area in square meters at the given resolution.
     pub fn hex_area(self) -> f64 {
         unsafe { h3_sys::hexAreaM2(self as i32) }
     }
 
+    /// Average hexagon area in square kilometers at the given resolution.
+    pub fn hex_area_km2(self) -> f64 {
+        unsafe { h3_sys::hexAreaKm2(self as i32) }
+    }
+
+    /// Tolerance in degrees for comparing coordinates at the given resolution,
+    /// taken as one millionth of the average hexagon edge length.
+    pub fn coordinate_tolerance(self) -> f64 {
+        self.edge_length() / METERS_PER_DEGREE * 1.0e-6
+    }
+
     /// Number of unique H3 indexes at the given resolution.
     pub fn num_hexagons(self) -> i64 {
         unsafe { h3_sys::numHexagons(self as i32) }
     }
 }
 
+/// An inclusive band of resolutions, from coarse to fine, e.g., the levels of
+/// a pyramid. Iterates from start to end.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResolutionRange {
+    start: GridResolution,
+    end: GridResolution,
+}
+
+impl ResolutionRange {
+    /// Returns the range from start to end, inclusive. Returns error if start
+    /// is finer than end.
+    pub fn new(start: GridResolution, end: GridResolution) -> Result<Self> {
+        if start <= end {
+            Ok(ResolutionRange { start, end })
+        } else {
+            Err(Error::InvalidResolutionRange(start, end))
+        }
+    }
+
+    /// The coarsest resolution in the range.
+    pub fn start(&self) -> GridResolution {
+        self.start
+    }
+
+    /// The finest resolution in the range.
+    pub fn end(&self) -> GridResolution {
+        self.end
+    }
+
+    /// Is the resolution within the range?
+    pub fn contains(&self, res: GridResolution) -> bool {
+        self.start <= res && res <= self.end
+    }
+}
+
+impl IntoIterator for ResolutionRange {
+    type Item = GridResolution;
+    type IntoIter =
+        std::iter::FilterMap<std::ops::RangeInclusive<i32>, fn(i32) -> Option<GridResolution>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (self.start as i32..=self.end as i32).filter_map(GridResolution::from_i32)
+    }
+}
+
+impl TryFrom<i32> for GridResolution {
+    type Error = Error;
+
+    fn try_from(i: i32) -> Result<Self> {
+        GridResolution::from_i32(i).ok_or(Error::InvalidResolutionArgument(i))
+    }
+}
+
 impl std::str::FromStr for GridResolution {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
         let res_val = s.parse::<i32>()?;
-        GridResolution::from_i32(res_val).ok_or(Error::InvalidResolutionArgument(res_val))
+        GridResolution::try_from(res_val)
     }
 }
 
@@ -107,5 +197,79 @@ mod tests {
         assert_relative_eq!(GridResolution::Z1.edge_length(), 418676.0055);
         assert_eq!(GridResolution::Z1.num_hexagons(), 842);
         assert_relative_eq!(GridResolution::Z1.hex_area(), 607221000000.0);
+        assert_relative_eq!(GridResolution::Z1.edge_length_km(), 418.6760055);
+        assert_relative_eq!(GridResolution::Z1.hex_area_km2(), 607220.9782);
+    }
+
+    #[test]
+    fn test_inherent_conversions() {
+        for i in 0..=MAX_GRID_RESOLUTION {
+            assert_eq!(
+                GridResolution::from_i32(i).map(GridResolution::as_i32),
+                Some(i)
+            );
+        }
+        assert_eq!(GridResolution::from_i32(-1), None);
+        assert_eq!(GridResolution::from_i32(16), None);
+        assert_eq!(GridResolution::Z9.as_i32(), 9);
+    }
+
+    #[test]
+    fn test_try_from_i32() {
+        assert_eq!(GridResolution::try_from(0), Ok(GridResolution::Z0));
+        assert_eq!(GridResolution::try_from(15), Ok(GridResolution::Z15));
+        assert_eq!(
+            GridResolution::try_from(16),
+            Err(Error::InvalidResolutionArgument(16))
+        );
+        assert_eq!(
+            GridResolution::try_from(-1),
+            Err(Error::InvalidResolutionArgument(-1))
+        );
+    }
+
+    #[test]
+    fn test_iter() {
+        let all: Vec<GridResolution> = GridResolution::iter().collect();
+        assert_eq!(all.len(), 16);
+        assert_eq!(all.first(), Some(&GridResolution::Z0));
+        assert_eq!(all.last(), Some(&GridResolution::Z15));
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_resolution_range() {
+        let range = ResolutionRange::new(GridResolution::Z5, GridResolution::Z9).unwrap();
+        let pyramid: Vec<GridResolution> = range.into_iter().collect();
+        assert_eq!(
+            pyramid,
+            vec![
+                GridResolution::Z5,
+                GridResolution::Z6,
+                GridResolution::Z7,
+                GridResolution::Z8,
+                GridResolution::Z9,
+            ]
+        );
+        assert!(range.contains(GridResolution::Z7));
+        assert!(!range.contains(GridResolution::Z10));
+        let single = ResolutionRange::new(GridResolution::Z0, GridResolution::Z0).unwrap();
+        assert_eq!(single.into_iter().count(), 1);
+        assert_eq!(
+            ResolutionRange::new(GridResolution::Z9, GridResolution::Z5),
+            Err(Error::InvalidResolutionRange(
+                GridResolution::Z9,
+                GridResolution::Z5
+            ))
+        );
+    }
+
+    #[test]
+    fn test_coordinate_tolerance() {
+        let all: Vec<GridResolution> = GridResolution::iter().collect();
+        for pair in all.windows(2) {
+            assert!(pair[0].coordinate_tolerance() > pair[1].coordinate_tolerance());
+        }
+        assert!(GridResolution::Z5.coordinate_tolerance() < 1.0e-6);
     }
 }
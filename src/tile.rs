@@ -0,0 +1,148 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Web Mercator tile conversions
+//!
+//! Functions for moving between H3 cells and the XYZ ("slippy map") tiles
+//! served by standard web map tile endpoints.
+
+use crate::errors::*;
+use crate::region::*;
+use crate::resolution::*;
+use crate::types::*;
+
+use geo_types::{Point, Polygon};
+use std::f64::consts::PI;
+
+/// The finest zoom level supported. Slippy map tile servers stop well short
+/// of this, and it keeps tile coordinates within a u32.
+pub const MAX_ZOOM: u8 = 30;
+
+/// Returns the (x, y) tile at the given zoom containing the point.
+fn point_to_tile(p: Point<f64>, zoom: u8) -> (u32, u32) {
+    let n = f64::from(1u32 << zoom);
+    let lat = p.lat().to_radians();
+    let x = (p.lng() + 180.) / 360. * n;
+    let y = (1. - (lat.tan() + 1. / lat.cos()).ln() / PI) / 2. * n;
+    let clamp = |v: f64| v.max(0.).min(n - 1.) as u32;
+    (clamp(x), clamp(y))
+}
+
+/// Returns the northwest corner of the tile.
+fn tile_to_point(zoom: u8, x: u32, y: u32) -> Point<f64> {
+    let n = f64::from(1u32 << zoom);
+    let lng = f64::from(x) / n * 360. - 180.;
+    let lat = (PI * (1. - 2. * f64::from(y) / n)).sinh().atan();
+    Point::new(lng, lat.to_degrees())
+}
+
+impl H3Index {
+    /// Returns the (x, y) Web Mercator tile at the given zoom level containing
+    /// the centroid of the index. Returns error if the zoom is beyond
+    /// `MAX_ZOOM`.
+    pub fn to_web_mercator_tile(&self, zoom: u8) -> Result<(u32, u32)> {
+        if zoom > MAX_ZOOM {
+            return Err(Error::InvalidZoom(zoom));
+        }
+        Ok(point_to_tile(self.centroid(), zoom))
+    }
+}
+
+/// Returns H3Index's whose centroids fall within the bounds of the given
+/// Web Mercator tile. Returns error if the tile doesn't exist.
+pub fn cells_in_tile(z: u8, x: u32, y: u32, res: GridResolution) -> Result<Vec<H3Index>> {
+    if z > MAX_ZOOM || x >= 1 << z || y >= 1 << z {
+        return Err(Error::InvalidTile(z, x, y));
+    }
+    let nw = tile_to_point(z, x, y);
+    let se = tile_to_point(z, x + 1, y + 1);
+    // H3 assumes any polygon edge spanning 180 degrees or more of longitude
+    // crosses the antimeridian, so tiles at zoom 0 and 1 are filled as
+    // narrower strips.
+    let strips = ((se.lng() - nw.lng()) / 90.).ceil().max(1.) as u32;
+    let width = (se.lng() - nw.lng()) / f64::from(strips);
+    let mut cells = Vec::new();
+    for i in 0..strips {
+        let west = nw.lng() + f64::from(i) * width;
+        let east = west + width;
+        let bounds = Polygon::new(
+            vec![
+                (west, nw.lat()),
+                (east, nw.lat()),
+                (east, se.lat()),
+                (west, se.lat()),
+            ]
+            .into(),
+            vec![],
+        );
+        cells.extend(polyfill_cells(&bounds, res));
+    }
+    cells.sort();
+    cells.dedup();
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_web_mercator_tile() {
+        let index = Point::new(-122.4194, 37.7749)
+            .to_h3_index(GridResolution::Z9)
+            .unwrap();
+        assert_eq!(index.to_web_mercator_tile(10), Ok((163, 395)));
+        assert_eq!(index.to_web_mercator_tile(0), Ok((0, 0)));
+        let (x, y) = index.to_web_mercator_tile(MAX_ZOOM).unwrap();
+        assert!(x < 1 << MAX_ZOOM && y < 1 << MAX_ZOOM);
+        assert_eq!(index.to_web_mercator_tile(32), Err(Error::InvalidZoom(32)));
+    }
+
+    #[test]
+    fn test_cells_in_tile() {
+        let cells = cells_in_tile(10, 163, 395, GridResolution::Z7).unwrap();
+        assert!(!cells.is_empty());
+        for cell in cells {
+            assert_eq!(cell.to_web_mercator_tile(10), Ok((163, 395)));
+        }
+        let res = GridResolution::Z0;
+        assert!(cells_in_tile(2, 3, 3, res).is_ok());
+        assert!(!cells_in_tile(0, 0, 0, GridResolution::Z2)
+            .unwrap()
+            .is_empty());
+        assert!(!cells_in_tile(1, 0, 0, GridResolution::Z2)
+            .unwrap()
+            .is_empty());
+        assert!(!cells_in_tile(1, 1, 1, GridResolution::Z2)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            cells_in_tile(2, 4, 0, res),
+            Err(Error::InvalidTile(2, 4, 0))
+        );
+        assert_eq!(
+            cells_in_tile(2, 0, 4, res),
+            Err(Error::InvalidTile(2, 0, 4))
+        );
+        assert_eq!(
+            cells_in_tile(MAX_ZOOM, u32::MAX, 0, res),
+            Err(Error::InvalidTile(MAX_ZOOM, u32::MAX, 0))
+        );
+        assert_eq!(
+            cells_in_tile(32, 0, 0, res),
+            Err(Error::InvalidTile(32, 0, 0))
+        );
+    }
+}
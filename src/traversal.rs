@@ -19,8 +19,32 @@
 //! determining how to traverse the grid from one cell to another.
 
 use crate::errors::*;
+use crate::index::unit_vector_to_point;
+use crate::measure::*;
+use crate::raw::fill_buffer;
+use crate::resolution::*;
 use crate::types::*;
 
+use geo_types::{LineString, Point};
+use std::collections::HashSet;
+
+/// The k-ring of an index, along with how complete it is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KRingReport {
+    /// The indexes within distance k.
+    pub cells: Vec<H3Index>,
+    /// Whether the k-ring contains, or is distorted by, a pentagon.
+    pub pentagon_affected: bool,
+    /// How many fewer indexes there are than in a k-ring without pentagons.
+    pub missing_slots: usize,
+}
+
+/// Maximum number of indexes within distance k of an index, i.e., the number
+/// of indexes in a k-ring without pentagons.
+pub fn max_k_ring_size(k: i32) -> usize {
+    unsafe { h3_sys::maxKringSize(k) as usize }
+}
+
 impl H3Index {
     /// Get H3 indices (or 'k-ring') within distance k of the given
     /// index. k-ring 0 is defined as the origin index, k-ring 1 is defined as
@@ -28,25 +52,26 @@ impl H3Index {
     pub fn k_ring_indices(&self, k: i32) -> Vec<H3Index> {
         // Get the maximum number of indices that result from the kRing
         // algorithm with the given k.
-        let k_ring_size = unsafe { h3_sys::maxKringSize(k) } as usize;
-        // TODO(mookerji): Verify that this coercion below is safe with H3Index.
-        let mut buf = Vec::<H3Index>::with_capacity(k_ring_size);
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            h3_sys::kRing(self.0, k, ptr as *mut h3_sys::H3Index);
-            // TODO(mookerji): figure out how to deal with .clone() / borrowed
-            // content here.
-            Vec::from_raw_parts(ptr, k_ring_size, k_ring_size)
-                .iter()
-                .filter_map(|i| {
-                    if *i != H3Index(0) {
-                        Some(i.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect()
+        let k_ring_size = max_k_ring_size(k);
+        // Near pentagons h3 fills fewer slots, leaving the rest untouched, so
+        // the buffer is zeroed and the empty slots filtered out.
+        let buf = fill_buffer(k_ring_size, |buf: *mut H3Index| unsafe {
+            h3_sys::kRing(self.0, k, buf as *mut h3_sys::H3Index);
+        });
+        buf.into_iter().filter(|i| *i != H3Index(0)).collect()
+    }
+
+    /// Returns the k-ring along with whether pentagons affect it and how many
+    /// indexes are missing relative to a k-ring without pentagons.
+    pub fn k_ring_report(&self, k: i32) -> KRingReport {
+        let cells = self.k_ring_indices(k);
+        let max_size = max_k_ring_size(k);
+        // hexRange fails when it encounters pentagonal distortion.
+        let pentagon_affected = cells.iter().any(|c| c.is_pentagon()) || self.hex_range(k).is_err();
+        KRingReport {
+            missing_slots: max_size.saturating_sub(cells.len()),
+            pentagon_affected,
+            cells,
         }
     }
 
@@ -55,26 +80,13 @@ impl H3Index {
     pub fn k_ring_distances(&self, k: i32) -> Vec<Vec<H3Index>> {
         // Get the maximum number of indices that result from the kRing
         // algorithm with the given k.
-        let k_ring_size = unsafe { h3_sys::maxKringSize(k) } as usize;
-        // TODO(mookerji): Verify that this coercion below is safe with H3Index.
-        let mut h3_buf = Vec::<H3Index>::with_capacity(k_ring_size);
-        let h3_ptr = h3_buf.as_mut_ptr();
-        let mut distance_buf = Vec::<i32>::with_capacity(k_ring_size);
-        let distance_ptr = distance_buf.as_mut_ptr();
-        let (indices, distances) = unsafe {
-            std::mem::forget(h3_buf);
-            std::mem::forget(distance_buf);
-            h3_sys::kRingDistances(
-                self.0,
-                k,
-                h3_ptr as *mut h3_sys::H3Index,
-                distance_ptr as *mut i32,
-            );
-            (
-                Vec::from_raw_parts(h3_ptr, k_ring_size, k_ring_size),
-                Vec::from_raw_parts(distance_ptr, k_ring_size, k_ring_size),
-            )
-        };
+        let k_ring_size = max_k_ring_size(k);
+        let mut distances = vec![];
+        let indices = fill_buffer(k_ring_size, |indices: *mut H3Index| {
+            distances = fill_buffer(k_ring_size, |distances| unsafe {
+                h3_sys::kRingDistances(self.0, k, indices as *mut h3_sys::H3Index, distances);
+            });
+        });
         let distance_size = *distances.iter().max().unwrap() as usize + 1;
         let mut result = vec![Vec::new(); distance_size];
         for i in 0..k_ring_size {
@@ -98,22 +110,45 @@ impl H3Index {
         }
     }
 
-    /// Return the line of indexes to another H3 index. Returns error if the
-    /// line cannot be computed.
-    pub fn line_to(&self, other: &H3Index) -> Result<Vec<H3Index>> {
-        let line_size = self.line_size(other)?;
-        let mut buf = Vec::<H3Index>::with_capacity(line_size);
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            h3_sys::h3Line(self.0, other.0, ptr as *mut h3_sys::H3Index);
-            Ok(Vec::from_raw_parts(ptr, line_size, line_size))
+    /// Returns grid distance to another H3Index, which may be at a different
+    /// resolution. Both indexes are first normalized to the coarser of their
+    /// resolutions (via `parent`), since that's the only common resolution at
+    /// which each has a unique cell; the distance is measured in cells of that
+    /// resolution.
+    pub fn distance_to_normalized(&self, other: &H3Index) -> Result<i32> {
+        match (self.resolution(), other.resolution()) {
+            (Some(res), Some(other_res)) => {
+                let coarser = res.min(other_res);
+                self.parent(coarser).distance_to(other.parent(coarser))
+            }
+            _ => Err(Error::IncompatibleIndices(self.clone(), other.clone())),
         }
     }
 
+    /// Is the other index within grid distance k of this index? Uses the grid
+    /// distance when it can be computed, falling back to searching the k-ring
+    /// for pairs separated by pentagonal distortion.
+    pub fn within_k(&self, other: &H3Index, k: i32) -> bool {
+        match self.distance_to(other.clone()) {
+            Ok(distance) => distance <= k,
+            Err(_) => self.k_ring_indices(k).contains(other),
+        }
+    }
+
+    /// Return the line of indexes to another H3 index, including both ends.
+    /// Returns error if the line cannot be computed.
+    pub fn line_to(&self, other: &H3Index) -> Result<Vec<H3Index>> {
+        let line_size = self.grid_path_size(other)?;
+        let mut line = fill_buffer(line_size, |buf: *mut H3Index| unsafe {
+            h3_sys::h3Line(self.0, other.0, buf as *mut h3_sys::H3Index);
+        });
+        line.retain(|i| *i != H3Index(0));
+        Ok(line)
+    }
+
     /// Number of indexes in a line from the this index to the end
     /// index. Returns error if the line cannot be computed.
-    fn line_size(&self, other: &H3Index) -> Result<usize> {
+    pub fn grid_path_size(&self, other: &H3Index) -> Result<usize> {
         let distance = unsafe { h3_sys::h3LineSize(self.0, other.0) };
         if distance < 0 {
             Err(Error::UnableToComputeH3Line(self.clone(), other.clone()))
@@ -123,34 +158,123 @@ impl H3Index {
     }
 
     /// Produces the hollow hexagonal ring centered at origin with sides of length k.
+    /// Returns error if k is negative.
     pub fn hex_ring(&self, k: i32) -> Result<Vec<H3Index>> {
-        let hex_ring_size = unsafe { h3_sys::maxKringSize(k) } as usize;
-        let mut buf = Vec::<H3Index>::with_capacity(hex_ring_size);
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            let err = h3_sys::hexRing(self.0, k, ptr as *mut h3_sys::H3Index);
-            if err == 0 {
-                Ok(Vec::from_raw_parts(ptr, hex_ring_size, hex_ring_size))
-            } else {
-                Err(Error::UnableToComputeTraversal(self.clone(), k))
+        if k < 0 {
+            return Err(Error::UnableToComputeTraversal(self.clone(), k));
+        }
+        let hex_ring_size = if k == 0 { 1 } else { 6 * k as usize };
+        let mut err = 0;
+        let buf = fill_buffer(hex_ring_size, |buf: *mut H3Index| {
+            err = unsafe { h3_sys::hexRing(self.0, k, buf as *mut h3_sys::H3Index) };
+        });
+        if err == 0 {
+            Ok(buf)
+        } else {
+            Err(Error::UnableToComputeTraversal(self.clone(), k))
+        }
+    }
+
+    /// Returns the immediate neighbors sorted clockwise by the bearing of their
+    /// centroids from this index's centroid, starting from due north.
+    pub fn neighbors_clockwise(&self) -> Vec<H3Index> {
+        let origin = self.centroid();
+        let mut neighbors: Vec<(f64, H3Index)> = self
+            .k_ring_indices(1)
+            .into_iter()
+            .filter(|n| n != self)
+            .map(|n| (bearing(origin, n.centroid()), n))
+            .collect();
+        neighbors.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        neighbors.into_iter().map(|(_, n)| n).collect()
+    }
+
+    /// Returns the sequence of cells along the great circle leaving this
+    /// index's centroid at the given compass bearing (degrees clockwise from
+    /// north), for a length of the given number of rings, i.e., cell spacings.
+    /// Unlike `line_to`, which follows the grid, this follows the geography.
+    /// Cells are found by indexing points sampled every quarter edge length.
+    pub fn ray_cells(&self, bearing_deg: f64, length_k: u32) -> Vec<H3Index> {
+        let res = match self.resolution() {
+            Some(res) => res,
+            None => return vec![],
+        };
+        let origin = self.centroid();
+        // Neighboring centroids are about sqrt(3) edge lengths apart.
+        let length = f64::from(length_k) * 3f64.sqrt() * res.edge_length();
+        let step = res.edge_length() / 4.;
+        let num_samples = (length / step).ceil() as usize;
+        let mut cells = vec![self.clone()];
+        for i in 1..=num_samples {
+            let sample = destination(origin, bearing_deg, length * i as f64 / num_samples as f64);
+            if let Ok(cell) = sample.to_h3_index(res) {
+                if cells.last() != Some(&cell) {
+                    cells.push(cell);
+                }
             }
         }
+        cells
+    }
+
+    /// Returns the sorted indexes at exactly grid distance k, found by removing
+    /// the (k - 1)-disk from the k-disk. Unlike `hex_ring`, this is safe
+    /// around pentagons.
+    pub fn ring_at(&self, k: i32) -> Vec<H3Index> {
+        if k < 0 {
+            return vec![];
+        }
+        let inner: HashSet<H3Index> = if k == 0 {
+            HashSet::new()
+        } else {
+            self.k_ring_indices(k - 1).into_iter().collect()
+        };
+        let mut ring: Vec<H3Index> = self
+            .k_ring_indices(k)
+            .into_iter()
+            .filter(|i| !inner.contains(i))
+            .collect();
+        ring.sort();
+        ring
+    }
+
+    /// Returns the sorted indexes reachable from this index by stepping to
+    /// neighbors for which `passable` returns true, within max_k steps (a
+    /// breadth-first flood fill). The origin is always included.
+    pub fn flood_fill<F: Fn(&H3Index) -> bool>(&self, max_k: i32, passable: F) -> Vec<H3Index> {
+        let mut visited: HashSet<H3Index> = HashSet::new();
+        visited.insert(self.clone());
+        let mut frontier = vec![self.clone()];
+        for _ in 0..max_k {
+            let mut next = Vec::new();
+            for cell in &frontier {
+                for neighbor in cell.k_ring_indices(1) {
+                    if !visited.contains(&neighbor) && passable(&neighbor) {
+                        visited.insert(neighbor.clone());
+                        next.push(neighbor);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        let mut result: Vec<H3Index> = visited.into_iter().collect();
+        result.sort();
+        result
     }
 
     /// Hexagons neighbors in all directions, assuming no pentagons.
     pub fn hex_range(&self, k: i32) -> Result<Vec<H3Index>> {
-        let hex_range_size = unsafe { h3_sys::maxKringSize(k) } as usize;
-        let mut buf = Vec::<H3Index>::with_capacity(hex_range_size);
-        let ptr = buf.as_mut_ptr();
-        unsafe {
-            std::mem::forget(buf);
-            let err = h3_sys::hexRange(self.0, k, ptr as *mut h3_sys::H3Index);
-            if err == 0 {
-                Ok(Vec::from_raw_parts(ptr, hex_range_size, hex_range_size))
-            } else {
-                Err(Error::UnableToComputeTraversal(self.clone(), k))
-            }
+        let hex_range_size = max_k_ring_size(k);
+        let mut err = 0;
+        let buf = fill_buffer(hex_range_size, |buf: *mut H3Index| {
+            err = unsafe { h3_sys::hexRange(self.0, k, buf as *mut h3_sys::H3Index) };
+        });
+        if err == 0 {
+            Ok(buf)
+        } else {
+            Err(Error::UnableToComputeTraversal(self.clone(), k))
         }
     }
 
@@ -158,25 +282,13 @@ impl H3Index {
     /// behavior is undefined when one of the indexes returned by this function
     /// is a pentagon or is in the pentagon distortion area.
     pub fn hex_range_distances(self, k: i32) -> Result<Vec<Vec<H3Index>>> {
-        let hex_range_size = unsafe { h3_sys::maxKringSize(k) } as usize;
-        let mut h3_buf = Vec::<H3Index>::with_capacity(hex_range_size);
-        let h3_ptr = h3_buf.as_mut_ptr();
-        let mut distance_buf = Vec::<i32>::with_capacity(hex_range_size);
-        let distance_ptr = distance_buf.as_mut_ptr();
-        let (indices, distances) = unsafe {
-            std::mem::forget(h3_buf);
-            std::mem::forget(distance_buf);
-            h3_sys::hexRangeDistances(
-                self.0,
-                k,
-                h3_ptr as *mut h3_sys::H3Index,
-                distance_ptr as *mut i32,
-            );
-            (
-                Vec::from_raw_parts(h3_ptr, hex_range_size, hex_range_size),
-                Vec::from_raw_parts(distance_ptr, hex_range_size, hex_range_size),
-            )
-        };
+        let hex_range_size = max_k_ring_size(k);
+        let mut distances = vec![];
+        let indices = fill_buffer(hex_range_size, |indices: *mut H3Index| {
+            distances = fill_buffer(hex_range_size, |distances| unsafe {
+                h3_sys::hexRangeDistances(self.0, k, indices as *mut h3_sys::H3Index, distances);
+            });
+        });
         let distance_size = *distances.iter().max().unwrap() as usize + 1;
         let mut result = vec![Vec::new(); distance_size];
         for i in 0..hex_range_size {
@@ -189,6 +301,201 @@ impl H3Index {
     }
 }
 
+/// Initial great-circle bearing in degrees from a to b, clockwise from north in
+/// [0, 360).
+fn bearing(a: Point<f64>, b: Point<f64>) -> f64 {
+    let (lat1, lat2) = (a.lat().to_radians(), b.lat().to_radians());
+    let dlng = (b.lng() - a.lng()).to_radians();
+    let y = dlng.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlng.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.)
+}
+
+/// The point reached by travelling the given distance in meters along a great
+/// circle from a, with the given initial bearing in degrees.
+fn destination(a: Point<f64>, bearing_deg: f64, distance_m: f64) -> Point<f64> {
+    let (lat1, lng1) = (a.lat().to_radians(), a.lng().to_radians());
+    let (theta, delta) = (
+        bearing_deg.to_radians(),
+        distance_m / METERS_PER_DEGREE.to_degrees(),
+    );
+    let lat2 = (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * theta.cos()).asin();
+    let lng2 = lng1
+        + (theta.sin() * delta.sin() * lat1.cos()).atan2(delta.cos() - lat1.sin() * lat2.sin());
+    let lng2 = (lng2.to_degrees() + 180.).rem_euclid(360.) - 180.;
+    Point::new(lng2, lat2.to_degrees())
+}
+
+/// Returns the cells within grid distance k of the line of indexes from a to
+/// b. Returns error if the line cannot be computed.
+pub fn line_corridor(a: &H3Index, b: &H3Index, k: u32) -> Result<Vec<H3Index>> {
+    let mut corridor = HashSet::new();
+    for cell in a.line_to(b)? {
+        corridor.extend(cell.k_ring_indices(k as i32));
+    }
+    let mut result: Vec<H3Index> = corridor.into_iter().collect();
+    result.sort();
+    Ok(result)
+}
+
+/// Returns the pairs of neighboring indexes within the set. Each undirected
+/// pair is reported once, ordered (lesser, greater).
+pub fn adjacency_graph(cells: &[H3Index]) -> Vec<(H3Index, H3Index)> {
+    let members: HashSet<&H3Index> = cells.iter().collect();
+    let mut edges = Vec::new();
+    for cell in &members {
+        for neighbor in cell.k_ring_indices(1) {
+            if **cell < neighbor && members.contains(&neighbor) {
+                edges.push(((*cell).clone(), neighbor));
+            }
+        }
+    }
+    edges.sort();
+    edges
+}
+
+/// Returns the pairs of neighboring indexes within the set, as in
+/// `adjacency_graph`, each weighted by the great-circle distance in meters
+/// between their centroids.
+pub fn weighted_adjacency(cells: &[H3Index]) -> Vec<(H3Index, H3Index, f64)> {
+    adjacency_graph(cells)
+        .into_iter()
+        .map(|(a, b)| {
+            let weight = point_distance_m(&a.centroid(), &b.centroid());
+            (a, b, weight)
+        })
+        .collect()
+}
+
+/// Returns the greatest grid distance from a cell in either set to the nearest
+/// cell in the other, i.e., the discrete Hausdorff distance between the
+/// coverings. Identical sets are at distance 0. Returns `IncompatibleIndices`
+/// if the cells aren't all at one resolution, or if a cell has no measurable
+/// distance to the other set (e.g., across a pentagon, or to an empty set).
+pub fn covering_distance(a: &[H3Index], b: &[H3Index]) -> Result<i32> {
+    let mut cells = a.iter().chain(b);
+    if let Some(first) = cells.next() {
+        let res = first.resolution();
+        if let Some(other) = cells.find(|i| i.resolution() != res) {
+            return Err(Error::IncompatibleIndices(first.clone(), other.clone()));
+        }
+    }
+    let directed = |from: &[H3Index], to: &[H3Index]| -> Result<i32> {
+        let mut max = 0;
+        for cell in from {
+            let nearest = to
+                .iter()
+                .filter_map(|other| cell.distance_to(other.clone()).ok())
+                .min()
+                .ok_or_else(|| {
+                    let other = to.first().cloned().unwrap_or_default();
+                    Error::IncompatibleIndices(cell.clone(), other)
+                })?;
+            max = max.max(nearest);
+        }
+        Ok(max)
+    };
+    Ok(directed(a, b)?.max(directed(b, a)?))
+}
+
+/// Groups the cells into maximal sets connected by adjacency (via union-find
+/// over neighboring pairs). Each component is sorted, and the components are
+/// ordered by their least index.
+pub fn connected_components(cells: &[H3Index]) -> Vec<Vec<H3Index>> {
+    let mut members: Vec<H3Index> = cells.to_vec();
+    members.sort();
+    members.dedup();
+    let mut parents: Vec<usize> = (0..members.len()).collect();
+    fn find(parents: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[i] = root;
+        root
+    }
+    for (a, b) in adjacency_graph(&members) {
+        let i = members.binary_search(&a).unwrap();
+        let j = members.binary_search(&b).unwrap();
+        let (ri, rj) = (find(&mut parents, i), find(&mut parents, j));
+        parents[ri.max(rj)] = ri.min(rj);
+    }
+    let mut components: Vec<Vec<H3Index>> = Vec::new();
+    let mut component_of_root = vec![usize::MAX; members.len()];
+    for (i, cell) in members.iter().enumerate() {
+        let root = find(&mut parents, i);
+        if component_of_root[root] == usize::MAX {
+            component_of_root[root] = components.len();
+            components.push(Vec::new());
+        }
+        components[component_of_root[root]].push(cell.clone());
+    }
+    components
+}
+
+/// Returns the cells at the given resolution whose centroids lie within
+/// `extra_m` meters beyond the bounding circle of the set, i.e., the circle
+/// centered on the mean of the member centroids that reaches the farthest
+/// member centroid. This expands the region by a physical buffer.
+pub fn cells_within_region_radius(
+    cells: &[H3Index],
+    extra_m: f64,
+    res: GridResolution,
+) -> Vec<H3Index> {
+    let vectors: Vec<[f64; 3]> = cells.iter().map(|c| c.to_cartesian()).collect();
+    let sum = vectors
+        .iter()
+        .fold([0.; 3], |s, v| [s[0] + v[0], s[1] + v[1], s[2] + v[2]]);
+    if vectors.is_empty() || sum == [0.; 3] {
+        return vec![];
+    }
+    let center = unit_vector_to_point(sum);
+    let radius = cells
+        .iter()
+        .map(|c| point_distance_m(&center, &c.centroid()))
+        .fold(0., f64::max)
+        + extra_m;
+    let origin = match center.to_h3_index(res) {
+        Ok(origin) => origin,
+        Err(_) => return vec![],
+    };
+    // Neighboring centroids are about sqrt(3) edge lengths apart, but the
+    // k-ring's flat sides are only 1.5 k edge lengths out, and cells near
+    // pentagons are smaller than average, so k is radius / edge length to
+    // leave margin for both.
+    let k = (radius / res.edge_length()).ceil() as i32 + 1;
+    let mut result: Vec<H3Index> = origin
+        .k_ring_indices(k)
+        .into_iter()
+        .filter(|c| point_distance_m(&center, &c.centroid()) <= radius)
+        .collect();
+    result.sort();
+    result
+}
+
+/// Returns the sequence of cells entered by the path, found by indexing points
+/// sampled along each segment at a quarter of the average edge length. Denser
+/// sampling is slower, but less likely to skip a cell whose corner the path
+/// barely clips.
+pub fn cells_crossed_by(line: &LineString<f64>, res: GridResolution) -> Vec<H3Index> {
+    let step = res.edge_length() / METERS_PER_DEGREE / 4.;
+    let mut cells: Vec<H3Index> = Vec::new();
+    for segment in line.lines() {
+        let (dx, dy) = (segment.dx(), segment.dy());
+        let num_samples = ((dx * dx + dy * dy).sqrt() / step).ceil().max(1.) as usize;
+        for i in 0..=num_samples {
+            let t = i as f64 / num_samples as f64;
+            let sample = Point::new(segment.start.x + t * dx, segment.start.y + t * dy);
+            if let Ok(cell) = sample.to_h3_index(res) {
+                if cells.last() != Some(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+    }
+    cells
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +520,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_max_k_ring_size() {
+        assert_eq!(max_k_ring_size(0), 1);
+        assert_eq!(max_k_ring_size(1), 7);
+        assert_eq!(max_k_ring_size(2), 19);
+    }
+
     #[test]
     fn test_k_ring2() {
         let k_ring = H3Index(0x8928308280fffff).k_ring_indices(2);
@@ -264,6 +578,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_k_ring_distances_pentagon() {
+        // h3 leaves slots untouched around a pentagon; none of them should
+        // surface as cells.
+        let pentagon = H3Index(0x821c07fffffffff);
+        let rings = pentagon.k_ring_distances(2);
+        assert_eq!(rings.len(), 3);
+        assert_eq!(rings[0], vec![pentagon.clone()]);
+        assert_eq!(rings[1].len(), 5);
+        let mut flattened: Vec<_> = rings.into_iter().flatten().collect();
+        assert!(flattened.iter().all(|i| *i != H3Index(0) && i.is_valid()));
+        let mut k_ring = pentagon.k_ring_indices(2);
+        flattened.sort();
+        k_ring.sort();
+        assert_eq!(flattened, k_ring);
+    }
+
+    #[test]
+    fn test_k_ring_report() {
+        let report = H3Index(0x821c07fffffffff).k_ring_report(1);
+        assert!(report.pentagon_affected);
+        assert_eq!(report.missing_slots, 1);
+        assert_eq!(report.cells.len(), 6);
+        let report = H3Index(0x8928308280fffff).k_ring_report(2);
+        assert!(!report.pentagon_affected);
+        assert_eq!(report.missing_slots, 0);
+        assert_eq!(report.cells.len(), 19);
+    }
+
+    #[test]
+    fn test_distance_to_normalized() {
+        let origin = H3Index(0x8928308280fffff);
+        let parent = origin.parent(GridResolution::Z8);
+        let neighbor = parent.ring_at(2)[0].clone();
+        assert!(origin.distance_to(neighbor.clone()).is_err());
+        assert_eq!(origin.distance_to_normalized(&neighbor), Ok(2));
+        assert_eq!(neighbor.distance_to_normalized(&origin), Ok(2));
+        assert_eq!(origin.distance_to_normalized(&parent), Ok(0));
+        let other = origin.ring_at(3)[0].clone();
+        assert_eq!(
+            origin.distance_to_normalized(&other),
+            origin.distance_to(other.clone())
+        );
+    }
+
     #[test]
     fn test_k_ring_distances() {
         let k_ring = H3Index(0x8928308280fffff).k_ring_distances(1);
@@ -305,9 +664,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_neighbors_clockwise() {
+        let origin = H3Index(0x8928308280fffff);
+        let neighbors = origin.neighbors_clockwise();
+        assert_eq!(neighbors.len(), 6);
+        let mut sorted = neighbors.clone();
+        sorted.sort();
+        assert_eq!(sorted, origin.ring_at(1));
+        let bearings: Vec<f64> = neighbors
+            .iter()
+            .map(|n| bearing(origin.centroid(), n.centroid()))
+            .collect();
+        assert!(bearings.windows(2).all(|w| w[0] < w[1]));
+        assert!(bearings.iter().all(|b| (0. ..360.).contains(b)));
+        assert_eq!(H3Index(0x821c07fffffffff).neighbors_clockwise().len(), 5);
+    }
+
+    #[test]
+    fn test_ring_at() {
+        let origin = H3Index(0x8928308280fffff);
+        let ring = origin.ring_at(1);
+        assert_eq!(ring.len(), 6);
+        assert!(!ring.contains(&origin));
+        let mut expected = vec![
+            H3Index(0x8928308280bffff),
+            H3Index(0x89283082807ffff),
+            H3Index(0x89283082877ffff),
+            H3Index(0x89283082803ffff),
+            H3Index(0x89283082873ffff),
+            H3Index(0x8928308283bffff),
+        ];
+        expected.sort();
+        assert_eq!(ring, expected);
+        assert_eq!(origin.ring_at(0), vec![origin.clone()]);
+        assert_eq!(origin.ring_at(2).len(), 12);
+        let pentagon = H3Index(0x821c07fffffffff);
+        let ring = pentagon.ring_at(1);
+        assert_eq!(ring.len(), 5);
+        assert!(!ring.contains(&pentagon));
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let origin = H3Index(0x8928308280fffff);
+        assert_eq!(
+            origin.flood_fill(3, |_| true).len(),
+            origin.k_ring_indices(3).len()
+        );
+        assert_eq!(origin.flood_fill(3, |_| false), vec![origin.clone()]);
+        // A north-south wall of impassable cells a few cells west of the
+        // origin, wide enough to have no gaps.
+        let edge = GridResolution::Z9.edge_length() / METERS_PER_DEGREE;
+        let lng = origin.centroid().lng();
+        let lng_scale = origin.centroid().lat().to_radians().cos();
+        let wall_east = lng - 2. * edge / lng_scale;
+        let wall_west = lng - 6. * edge / lng_scale;
+        let in_wall = |c: &H3Index| {
+            let x = c.centroid().lng();
+            wall_west <= x && x <= wall_east
+        };
+        let filled = origin.flood_fill(8, |c| !in_wall(c));
+        assert!(filled.iter().all(|c| c.centroid().lng() > wall_east));
+        // Expansion to the east is unobstructed.
+        let east = filled
+            .iter()
+            .map(|c| c.centroid().lng())
+            .fold(lng, f64::max);
+        assert!(east > lng + 8. * edge / lng_scale);
+    }
+
     #[test]
     fn test_hex_ring2() {
         let k_ring = H3Index(0x8928308280fffff).hex_ring(2).unwrap();
+        assert_eq!(k_ring.len(), 12);
         let expected_hexagons = vec![
             H3Index(0x89283082813ffff),
             H3Index(0x89283082817ffff),
@@ -326,4 +756,224 @@ mod tests {
             assert!(k_ring.contains(&hex));
         }
     }
+
+    #[test]
+    fn test_hex_ring_sizes() {
+        let origin = H3Index(0x8928308280fffff);
+        assert_eq!(origin.hex_ring(0), Ok(vec![origin.clone()]));
+        for k in 1..4 {
+            let ring = origin.hex_ring(k).unwrap();
+            assert_eq!(ring.len(), 6 * k as usize);
+            assert!(ring.iter().all(|i| *i != H3Index(0)));
+        }
+        assert_eq!(
+            origin.hex_ring(-1),
+            Err(Error::UnableToComputeTraversal(origin.clone(), -1))
+        );
+        // The ring around a pentagon is distorted, which h3 reports as an error.
+        let pentagon = H3Index(0x821c07fffffffff);
+        assert_eq!(
+            pentagon.hex_ring(1),
+            Err(Error::UnableToComputeTraversal(pentagon.clone(), 1))
+        );
+    }
+
+    #[test]
+    fn test_line_to() {
+        let start = H3Index(0x8928308280fffff);
+        let end = start.hex_ring(3).unwrap()[0].clone();
+        assert_eq!(start.distance_to(end.clone()), Ok(3));
+        assert_eq!(start.grid_path_size(&end), Ok(4));
+        let line = start.line_to(&end).unwrap();
+        assert_eq!(line.len(), 4);
+        assert_eq!(line[0], start);
+        assert_eq!(line[3], end);
+        assert!(line.iter().all(|i| i.is_valid()));
+        assert!(line.windows(2).all(|w| w[0].is_neighbor(&w[1])));
+        let far = H3Index(0x891ea6d6533ffff);
+        assert_eq!(
+            start.grid_path_size(&far),
+            Err(Error::UnableToComputeH3Line(start.clone(), far.clone()))
+        );
+        assert!(start.line_to(&far).is_err());
+    }
+
+    #[test]
+    fn test_ray_cells() {
+        let origin = H3Index(0x8928308280fffff);
+        let ray = origin.ray_cells(90., 5);
+        assert_eq!(ray[0], origin);
+        assert!(ray.windows(2).all(|w| w[0].is_neighbor(&w[1])));
+        let last = ray.last().unwrap();
+        assert!((4..=6).contains(&origin.distance_to(last.clone()).unwrap()));
+        // Each cell lies further east, and roughly due east of the origin.
+        let centroids: Vec<Point<f64>> = ray.iter().map(|c| c.centroid()).collect();
+        assert!(centroids.windows(2).all(|w| w[0].lng() < w[1].lng()));
+        for c in &centroids[1..] {
+            assert!((bearing(centroids[0], *c) - 90.).abs() < 30.);
+        }
+        assert_eq!(origin.ray_cells(0., 0), vec![origin.clone()]);
+        let north = origin.ray_cells(0., 5);
+        assert!(north.last().unwrap().centroid().lat() > centroids[0].lat());
+    }
+
+    #[test]
+    fn test_destination() {
+        let sf = Point::new(-122.4194, 37.7749);
+        let nyc = Point::new(-74.0060, 40.7128);
+        let p = destination(sf, bearing(sf, nyc), point_distance_m(&sf, &nyc));
+        assert_relative_eq!(p.lat(), nyc.lat(), epsilon = 1e-6);
+        assert_relative_eq!(p.lng(), nyc.lng(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_line_corridor() {
+        let start = H3Index(0x8928308280fffff);
+        let end = H3Index(0x89283082813ffff);
+        let line = start.line_to(&end).unwrap();
+        assert_eq!(line_corridor(&start, &end, 0).unwrap().len(), line.len());
+        let corridor = line_corridor(&start, &end, 1).unwrap();
+        for cell in &line {
+            assert!(corridor.contains(cell));
+        }
+        for cell in line[line.len() / 2].k_ring_indices(1) {
+            assert!(corridor.contains(&cell));
+        }
+    }
+
+    #[test]
+    fn test_adjacency_graph() {
+        let start = H3Index(0x8928308280fffff);
+        let end = H3Index(0x89283082813ffff);
+        let line = start.line_to(&end).unwrap();
+        assert_eq!(line.len(), 3);
+        let edges = adjacency_graph(&line);
+        assert_eq!(edges.len(), 2);
+        for (a, b) in &edges {
+            assert!(a < b);
+            assert!(line.contains(a) && line.contains(b));
+        }
+        assert!(adjacency_graph(&[start, end]).is_empty());
+    }
+
+    #[test]
+    fn test_cells_crossed_by() {
+        let line = LineString::from(vec![(-122.45, 37.75), (-122.40, 37.78)]);
+        let res = GridResolution::Z9;
+        let cells = cells_crossed_by(&line, res);
+        assert!(cells.len() > 10);
+        assert_eq!(
+            cells[0],
+            Point::new(-122.45, 37.75).to_h3_index(res).unwrap()
+        );
+        assert_eq!(
+            cells[cells.len() - 1],
+            Point::new(-122.40, 37.78).to_h3_index(res).unwrap()
+        );
+        for pair in cells.windows(2) {
+            assert_eq!(pair[0].distance_to(pair[1].clone()), Ok(1));
+        }
+    }
+
+    #[test]
+    fn test_within_k() {
+        let origin = H3Index(0x8928308280fffff);
+        let ring2 = origin.hex_ring(2).unwrap()[0].clone();
+        assert!(origin.within_k(&ring2, 2));
+        assert!(!origin.within_k(&ring2, 1));
+        assert!(origin.within_k(&origin, 0));
+        // Pentagon-adjacent pairs fall back to the k-ring search.
+        let pentagon = H3Index(0x821c07fffffffff);
+        let neighbor = pentagon.k_ring_indices(1)[1].clone();
+        assert!(pentagon.within_k(&neighbor, 1));
+    }
+
+    #[test]
+    fn test_covering_distance() {
+        let origin = H3Index(0x8928308280fffff);
+        let disk = origin.k_ring_indices(2);
+        assert_eq!(covering_distance(&disk, &disk), Ok(0));
+        assert_eq!(covering_distance(&[], &[]), Ok(0));
+        // Shifting the disk by one cell moves its far edge one cell out.
+        let shifted = origin.hex_ring(1).unwrap()[0].k_ring_indices(2);
+        assert_eq!(covering_distance(&disk, &shifted), Ok(1));
+        assert_eq!(covering_distance(&shifted, &disk), Ok(1));
+        // A subset is close in one direction only.
+        assert_eq!(
+            covering_distance(&disk, std::slice::from_ref(&origin)),
+            Ok(2)
+        );
+        let child = origin.children_ordered(GridResolution::Z10)[0].clone();
+        assert_eq!(
+            covering_distance(&disk, std::slice::from_ref(&child)),
+            Err(Error::IncompatibleIndices(disk[0].clone(), child))
+        );
+        assert!(covering_distance(&disk, &[]).is_err());
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let a = H3Index(0x8928308280fffff);
+        let b = a.hex_ring(4).unwrap()[0].clone();
+        let mut cells = a.k_ring_indices(1);
+        cells.extend(b.k_ring_indices(1));
+        let components = connected_components(&cells);
+        assert_eq!(components.len(), 2);
+        let mut expected_a = a.k_ring_indices(1);
+        expected_a.sort();
+        let mut expected_b = b.k_ring_indices(1);
+        expected_b.sort();
+        assert!(components.contains(&expected_a));
+        assert!(components.contains(&expected_b));
+        // Joining the clusters with a line merges the components.
+        cells.extend(a.line_to(&b).unwrap());
+        assert_eq!(connected_components(&cells).len(), 1);
+    }
+
+    #[test]
+    fn test_cells_within_region_radius() {
+        let res = GridResolution::Z9;
+        let cells = H3Index(0x8928308280fffff).k_ring_indices(2);
+        let expanded = cells_within_region_radius(&cells, 500., res);
+        assert!(cells.iter().all(|c| expanded.contains(c)));
+        assert!(expanded.len() > cells.len());
+        let unbuffered = cells_within_region_radius(&cells, 0., res);
+        assert!(cells.iter().all(|c| unbuffered.contains(c)));
+        assert!(unbuffered.len() < expanded.len());
+        assert!(cells_within_region_radius(&[], 500., res).is_empty());
+
+        // Cells near a pentagon are smaller than average, so the k-ring must
+        // reach further in them to cover the circle.
+        let pentagon = H3Index(0x821c07fffffffff).refine_center(7).unwrap();
+        let cell = pentagon.k_ring_indices(3).into_iter().last().unwrap();
+        let radius = 10_000.;
+        let covered = cells_within_region_radius(std::slice::from_ref(&cell), radius, res);
+        let centroid = cell.centroid();
+        let mut expected: Vec<H3Index> = cell
+            .k_ring_indices(100)
+            .into_iter()
+            .filter(|c| c.is_valid() && point_distance_m(&centroid, &c.centroid()) <= radius)
+            .collect();
+        expected.sort();
+        expected.dedup();
+        assert_eq!(covered, expected);
+    }
+
+    #[test]
+    fn test_weighted_adjacency() {
+        let cells = H3Index(0x8928308280fffff).k_ring_indices(1);
+        let edges = weighted_adjacency(&cells);
+        assert_eq!(edges.len(), adjacency_graph(&cells).len());
+        let edge_length = GridResolution::Z9.edge_length();
+        for (a, b, weight) in edges {
+            assert!(weight > 0.);
+            assert_relative_eq!(
+                weight,
+                point_distance_m(&b.centroid(), &a.centroid()),
+                epsilon = 1e-6
+            );
+            // Neighboring centroids are about sqrt(3) edge lengths apart.
+            assert!(weight > edge_length && weight < 3. * edge_length);
+        }
+    }
 }
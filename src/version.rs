@@ -0,0 +1,55 @@
+// Copyright 2016-2020 Uber Technologies, Inc.
+// Copyright 2020      Bhaskar Mookerji
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bound h3 library version
+//!
+//! Some h3 functions are only available in newer versions of the C library.
+//! These functions allow callers to detect them and fall back gracefully.
+
+/// Returns the (major, minor, patch) version of the h3 headers that `h3-sys`
+/// is bound against.
+pub fn h3_version() -> (u32, u32, u32) {
+    (
+        h3_sys::H3_VERSION_MAJOR,
+        h3_sys::H3_VERSION_MINOR,
+        h3_sys::H3_VERSION_PATCH,
+    )
+}
+
+/// Does the bound h3 version provide exact cell area and edge length
+/// functions (`cellAreaM2`, `exactEdgeLengthM`, etc.)? These were added in
+/// h3 3.7.0. This crate links them unconditionally, so it can't be built
+/// against an older h3, and this is always true; it's kept so callers written
+/// against other h3 bindings can check for the functions uniformly.
+pub fn has_exact_area_support() -> bool {
+    h3_version() >= (3, 7, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::H3Index;
+    use crate::resolution::GridResolution;
+
+    #[test]
+    fn test_has_exact_area_support() {
+        assert!(has_exact_area_support());
+        // The exact functions are callable, and differ from the averages.
+        let index = H3Index(0x8928308280fffff);
+        let area = index.area_m2();
+        assert!(area > 0.);
+        assert!((area - GridResolution::Z9.hex_area()).abs() > 1.);
+    }
+}